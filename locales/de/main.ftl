@@ -0,0 +1,373 @@
+## general
+
+bot-started = Ich bin gerade von den Toten auferstanden.
+err-msg = Hoppla, da ist mir was schiefgegangen!
+not-registered = Du bist noch nicht registriert. Verknüpfe deinen Benutzernamen mit /set [username].
+not-registered-inline = Konto verknüpfen
+welcome-text =
+    Willkommen bei LastFM Robot. Lege mit /set [username] deinen Benutzernamen fest.
+
+    Mit <a href="https://kawaiidango.github.io/pano-scrobbler">Pano Scrobbler</a> kannst du unter Windows, Linux oder Android scrobbeln
+
+    Manche Befehle funktionieren auch ohne /
+privacy-stored =
+    Der Bot LastFM Robot speichert eine Zuordnung der Telegram-ID und des Telegram-Benutzernamens eines Nutzers
+    zu dessen Benutzernamen beim Scrobbling-Dienst (Lastfm, Librefm oder ListenBrainz), die Bot-Einstellungen des Nutzers und wann er zuletzt einen Befehl benutzt hat.
+
+    Diese Daten werden verwendet, um die Scrobbles des Nutzers abzurufen und anzuzeigen.
+privacy-groups = In Gruppen merkt er sich, welche registrierten Nutzer ihn dort benutzt haben, für /tastematrix und @-Erwähnungen, sowie die Einstellungen der Gruppenadmins.
+privacy-analytics = Jeder Befehl und jeder Knopfdruck wird mit Namen, Zeit, der Telegram-Sprache des Nutzers und einer mit einem geheimen Salt aus seiner Telegram-ID gehashten ID gezählt, für allgemeine Statistiken.
+privacy-amplitude = Diese Zählungen gehen außerdem an Amplitude (amplitude.com).
+privacy-opt-out = Der Nutzer kann das mit /privacy off oder unter Nutzungsstatistiken in /preferences abschalten.
+privacy-opted-out = Der Nutzer hat das abgeschaltet, für ihn wird nichts gezählt. /privacy on schaltet es wieder ein.
+privacy-delete = Der Nutzer kann diese Daten löschen und sich vom Bot trennen, indem er bei /preferences auf Trennen klickt.
+anon-kun = Hallöchen, anon-kun
+bots-music = Wir Bots hören keine Musik, baaaaaka.
+its-me = Guck mal, das bin ja ich!!!
+no = Neeeeeeeeein!
+loading = Lädt...
+not-found = Nicht gefunden.
+user-not-found = Diesen Nutzer gibt es nicht
+private-profile = Deine Scrobbles sind versteckt. Um diesen Bot zu nutzen, ändere das unter https://www.last.fm/settings/privacy
+lastfm-down = Last.fm scheint gerade nicht erreichbar zu sein, bitte versuch es in einer Minute noch mal.
+librefm-down = Libre.fm scheint gerade nicht erreichbar zu sein, bitte versuch es in einer Minute noch mal.
+listenbrainz-down = ListenBrainz scheint gerade nicht erreichbar zu sein, bitte versuch es in einer Minute noch mal.
+no-scrobbles = Keine Scrobbles gefunden!
+message-unmodified = Nichts Neues in deinem Profil
+button-outdated = Dieser Button ist veraltet, führe den Befehl erneut aus
+message-too-old = Diese Nachricht ist zu alt und kann nicht bearbeitet werden
+group-only = Das funktioniert nur in Gruppen!
+admins-only = Das können nur Gruppenadmins!
+and = {" "}und{" "}
+
+## commands
+
+help-header = Diese Befehle werden unterstützt:
+cmd-status = Dein zuletzt gehörter Song
+cmd-status_full = Deine letzten 3 Songs mit Albumcover
+cmd-loved = Deine letzten 5 Lieblingstitel
+cmd-collage = Albumcover-Collage erstellen
+cmd-compat = Euer Kompatibilitätswert
+cmd-tastematrix = Kompatibilität aller in dieser Gruppe
+cmd-random = Ein zufälliger Top-Künstler, -Album oder -Titel
+cmd-topkek = Top-Künstler, -Alben oder -Titel als Text
+cmd-flex = Zeig deine Zahlen
+cmd-milestones = Meilenstein-Ankündigungen in dieser Gruppe
+cmd-personality = Wie ich in dieser Gruppe spreche
+cmd-chatsettings = Gruppeneinstellungen für Admins
+cmd-clock = Wann du Musik hörst
+cmd-genres = Deine Top-Genres
+cmd-wrapped = Dein Jahresrückblick
+cmd-plays = Deine Wiedergaben eines Titels, seines Albums und Künstlers
+cmd-artist = Statistiken zu einem Künstler
+cmd-album = Statistiken zu einem Album
+cmd-track = Statistiken zu einem Titel
+cmd-preferences = Deine Einstellungen für diesen Bot
+cmd-help = Hiiiiiilfe!
+cmd-privacy = Datenschutzerklärung
+
+collage-usage = Direkte Nutzung: <b>collage 3 1m, /collage clean 4 alltime, /collage 5 1y below ranks gaps rounded light header, /collage 4 1m rainbow, /collage 7 alltime png file</b> usw.
+top-usage = Direkte Nutzung: <b>/topkek artists 1m , /topkek tracks alltime, /topkek albums 1y chart, /topkek tracks 1m 25</b>
+random-usage = Direkte Nutzung: <b>/random artists 1m , /random tracks alltime</b>
+entity-usage =
+    Direkte Nutzung: <b>/track Künstler - Titel, /album Künstler - Album, /artist Künstler</b>
+
+    Ohne Angabe wird verwendet, was du gerade hörst.
+wrapped-usage = Nutzung: <b>/wrapped 2024</b>, oder einfach /wrapped für das bisherige Jahr.
+
+## inline mode
+
+inline-status = Status
+inline-status-text = Staaaaaaatus
+inline-status_full = Erweiterter Status
+inline-status_full-text = Erweiterter Staaaaatus
+inline-loved = Lieblingstitel
+inline-loved-text = Lieblingstitel
+inline-flex = Zeig deine Zahlen
+inline-flex-text = Zeig deine Zahlen
+inline-topkek = Top-Künstler, -Alben oder -Titel als Text
+inline-topkek-text = Top-Künstler, -Alben oder -Titel als Text
+inline-random = Scrobbles mischen
+inline-random-text = Scrobbles mischen
+inline-collage = Albumcover-Collage
+inline-collage-text = Albumcover-Collage
+inline-compat = Kompatibilität mit { $target }
+inline-compat-text = Kompatibilität
+
+## shared pieces
+
+count-plays = { $count } Wiedergaben
+count-artists = { $count } Künstler
+count-scrobbles = { $count } Scrobbles
+
+period-one-week = 1 Woche
+period-one-month = 1 Monat
+period-three-months = 3 Monate
+period-six-months = 6 Monate
+period-one-year = 1 Jahr
+period-all-time = Gesamtzeit
+
+entry-artist = Künstler
+entry-album = Album
+entry-track = Titel
+entries-artist = Künstler
+entries-album = Alben
+entries-track = Titel
+button-artist = Künstler
+button-album = Album
+button-track = Titel
+button-alltime = gesamt
+
+choose-type = Wähle eine Art:
+choose-period = Wähle einen Zeitraum:
+choose-size = Wähle eine Größe:
+
+weekday-0 = Montag
+weekday-1 = Dienstag
+weekday-2 = Mittwoch
+weekday-3 = Donnerstag
+weekday-4 = Freitag
+weekday-5 = Samstag
+weekday-6 = Sonntag
+weekday-short-0 = Mo
+weekday-short-1 = Di
+weekday-short-2 = Mi
+weekday-short-3 = Do
+weekday-short-4 = Fr
+weekday-short-5 = Sa
+weekday-short-6 = So
+
+month-1 = Januar
+month-2 = Februar
+month-3 = März
+month-4 = April
+month-5 = Mai
+month-6 = Juni
+month-7 = Juli
+month-8 = August
+month-9 = September
+month-10 = Oktober
+month-11 = November
+month-12 = Dezember
+month-short-1 = Jan.
+month-short-2 = Feb.
+month-short-3 = März
+month-short-4 = Apr.
+month-short-5 = Mai
+month-short-6 = Juni
+month-short-7 = Juli
+month-short-8 = Aug.
+month-short-9 = Sep.
+month-short-10 = Okt.
+month-short-11 = Nov.
+month-short-12 = Dez.
+date-short = { $day }. { $month }
+
+## status and loved
+
+status-now-listening = { $name } hört gerade
+status-was-listening = { $name } hörte zuletzt
+status-plays = , { $plays } Wiedergaben
+status-loved = , 💗 geliebt
+card-now = jetzt
+loved-heading = Lieblingstitel von { $name }:
+info-lastfm =
+    { $plays } Wiedergaben
+    { $listeners } 🌎 Hörer
+    { $scrobbles } 🌎 Scrobbles
+info-listenbrainz =
+    { $listeners } 🌎 Hörer
+    { $listens } 🌎 Wiedergaben
+info-track-failed = Titelinfos konnten nicht geladen werden
+
+## set and preferences
+
+set-click =
+    Nutzung: <b>/set username</b> legt deinen Benutzernamen für lastfm fest
+    <b>/set username listenbrainz</b> legt deinen Benutzernamen für listenbrainz fest
+set-done =
+    ✅Benutzername für { $service } festgelegt!
+
+    Unter /preferences kannst du Links zu deinem { $service }-Profil anzeigen oder beim Status immer das Albumcover zeigen lassen.
+
+    Nicht { $service }? Ändere deine Kontoart mit den Buttons.
+set-failed =
+    { $error }
+
+    { user-not-found } bei { $service }
+
+    Ändere deine Kontoart mit den Buttons.
+unset = Dein Benutzername wurde vom Bot getrennt.
+pref-profile-links = Profillinks
+pref-album-art = Immer Albumcover zeigen
+pref-milestones = Meilenstein-Benachrichtigungen
+pref-broadcasts = Ankündigungen vom Betreiber des Bots
+pref-analytics = Nutzungsstatistiken
+pref-unlink = Konto trennen
+pref-personality = Persönlichkeit: { $personality }
+timezone-usage = Ändern mit <b>/preferences timezone Europe/Berlin</b>, jeder IANA-Zeitzonenname funktioniert.
+locale-usage = Ändere die Zahlendarstellung mit <b>/preferences locale de</b>, etwa en, de, fr oder en-IN.
+language-usage = Ändere meine Sprache mit <b>/preferences language en</b>, oder <b>auto</b>, um deiner Telegram-App zu folgen. Ich spreche { $languages }.
+settings =
+    Einstellungen für { $name }
+
+    Zeitzone: { $timezone }
+    { timezone-usage }
+
+    Zahlen: { $numbers } ({ $locale })
+    { locale-usage }
+
+    Sprache: { $language }
+    { language-usage }
+
+    Persönlichkeit: { $personality }
+    { personality-usage }
+
+## personalities
+
+personality-uwu = uwu
+personality-plain = schlicht
+personality-default = die eigene Wahl jedes Mitglieds
+personality-usage = Ändere, wie ich spreche, mit <b>/preferences personality plain</b>, oder <b>auto</b> für die Voreinstellung. Zur Wahl stehen { $personalities }.
+personality-private = Wie ich mit dir spreche, änderst du in /preferences. Admins können mit <b>/personality plain</b> in einer Gruppe für alle dort wählen, zur Wahl stehen { $personalities }.
+personality-chat-usage = So spreche ich hier: { $personality }. Admins können das mit <b>/personality plain</b> ändern, oder mit <b>/personality default</b> jedem selbst überlassen. Zur Wahl stehen { $personalities }.
+personality-chat-set = Alle hier bekommen jetzt die Persönlichkeit „{ $personality }“.
+personality-chat-default = Alle hier bekommen jetzt die Persönlichkeit, die sie in /preferences gewählt haben.
+
+## chat settings
+
+chatsettings =
+    Einstellungen für diese Gruppe, nur Admins können sie ändern.
+
+    Befehle ohne /: { $slashless }
+    Collagen ohne Text: { $clean }
+    Standardzeitraum: { $period }
+    Themen: { $topics }
+    Abgeschaltet: { $disabled }
+
+    { chatsettings-usage }
+chatsettings-usage = Nutze die Knöpfe oder schreib etwa <b>/chatsettings disable collage</b>, <b>/chatsettings period 1m</b> (1w, 1m, 3m, 6m, 1y, alltime oder default), <b>/chatsettings clean on</b> oder <b>/chatsettings slashless off</b>. In einem Forum beschränkt mich <b>/chatsettings topic</b> auf die Themen, in denen es geschickt wurde.
+chatsettings-on = an
+chatsettings-off = aus
+chatsettings-none = nichts
+chatsettings-topics-all = alle
+chatsettings-topics-some = beschränkt auf { $count }
+chatsettings-period-none = jedes Mal fragen
+chatsettings-slashless = Befehle ohne /
+chatsettings-clean = Collagen ohne Text
+chatsettings-period = Zeitraum: { $period }
+chatsettings-topic = In diesem Thema aktiv
+
+## top, collage, random
+
+top-heading = Top-{ $entries } von { $name } für { $period }
+top-line = { $entry } -> { $plays } Wiedergaben
+collage-heading = Albumcollage von { $name } für { $period }
+collage-librefm = Collagen gibt es für Librefm nicht.
+random-heading = Zufälliger Eintrag ({ $entry }) von { $name } für { $period }
+random-entry =
+    { $entry }
+    ({ $plays } Wiedergaben)
+
+## flex and milestones
+
+flex-counts =
+    { $artists } Künstler
+    { $albums } Alben
+    { $tracks } Titel
+    { $plays } Wiedergaben
+flex-streak = { $days } Tage in Folge
+flex-next = noch { $plays } Wiedergaben bis { $milestone }
+flex-since = Seit { $date }
+milestone-announcement = 🎉 { $name } hat gerade { $reached } erreicht!
+milestone-notification = 🎉 Du hast gerade { $reached } erreicht!
+milestones-on = Ich feiere hier ab jetzt alle Scrobble-Meilensteine! Dafür müssen Meilenstein-Benachrichtigungen in /preferences eingeschaltet sein.
+milestones-off = Keine Meilenstein-Ankündigungen mehr in dieser Gruppe.
+milestones-private = Schalte Meilenstein-Benachrichtigungen in /preferences ein. Admins können mit /milestones in einer Gruppe festlegen, dass sie dort angekündigt werden.
+
+## clock, wrapped, genres
+
+clock-heading = Hör-Uhr von { $name } für { $period }
+clock-summary =
+    { $scrobbles } Scrobbles
+    Aktivste Stunde: { $hour } ({ $hour_scrobbles } Scrobbles)
+    Aktivster Tag: { $day }
+    Zeitzone: { $timezone }
+clock-summary-partial =
+    { $scrobbles } Scrobbles (nur die neuesten wurden gezählt)
+    Aktivste Stunde: { $hour } ({ $hour_scrobbles } Scrobbles)
+    Aktivster Tag: { $day }
+    Zeitzone: { $timezone }
+wrapped-heading = Jahresrückblick { $year } von { $name }
+wrapped-total = <b>{ $scrobbles }</b> Scrobbles
+wrapped-total-partial = <b>{ $scrobbles }</b> Scrobbles (nur die neuesten wurden gezählt)
+wrapped-scrobbles = Scrobbles
+wrapped-top-artists = Top-Künstler
+wrapped-top-albums = Top-Alben
+wrapped-top-tracks = Top-Titel
+wrapped-new-artists = Neue Künstler: { $count }
+wrapped-new-artists-like = { $count } neue Künstler, etwa
+wrapped-busiest-month = Aktivster Monat: { $month } ({ $scrobbles })
+wrapped-busiest-day = Aktivster Tag: { $day } ({ $scrobbles })
+wrapped-longest-streak = Längste Serie: { $days } Tage
+wrapped-longest-streak-range = Längste Serie: { $days } Tage, { $start } bis { $end }
+genres-heading = Top-Genres von { $name } für { $period }
+genres-other = andere
+no-genres = Keine Genres für deine Top-Künstler gefunden!
+
+## track, album, artist
+
+entity-plays-share = { $plays } Wiedergaben ({ $share } % aller Scrobbles)
+entity-global = { $listeners } 🌎 Hörer, { $scrobbles } 🌎 Scrobbles
+entity-scrobble-dates = Zuerst gescrobbelt am { $first }, zuletzt am { $last }
+entity-plays-heading = Wiedergaben von { $name }
+
+## compat and tastematrix
+
+compat-click = Nutzung: compat 1y. Antworte in einer Gruppe mit diesem Befehl auf die Nachricht von jemandem, oder nutze <b>/compat @username 1y</b> oder <b>/compat lastfm:username</b>. Im Inline-Modus tippe <b>compat @username</b> nach meinem Namen.
+they-not-registered = Diese Person muss erst mit /set ihren Benutzernamen festlegen.
+mention-not-registered = Die Person kenne ich noch nicht, sie muss mit /set ihren Benutzernamen festlegen und einen Befehl nutzen.
+compat-nothing-common = Keine gemeinsamen Künstler in { $period }
+compat-result =
+    { $name1 } und { $name2 } hören beide
+    { $artists }
+
+    Der Kompatibilitätswert ist { $score } %, basierend auf { $period }
+    Künstler { $artists_score }, Alben { $albums_score }, Titel { $tracks_score }
+tastematrix-heading = Geschmacksmatrix für { $period }
+tastematrix-line = { $name }: am ähnlichsten { $most }, am wenigsten { $least }
+tastematrix-few-members = Ich brauche hier mindestens zwei Mitglieder, die mit /set ihren Benutzernamen festgelegt und mich in dieser Gruppe genutzt haben.
+
+## owner
+
+owner-stats =
+    Registrierte Nutzer: { $users }
+    { $api_types }
+
+    Aktiv am letzten Tag: { $day }, in der letzten Woche: { $week }, im letzten Monat: { $month }
+
+    Ereignisse seit { $since } UTC, <b>/stats 7</b> für eine Woche:
+    { $commands }
+
+    Pro Tag:
+    { $daily }
+owner-stats-day = { $date }: { $events } Ereignisse von { $users } Nutzern
+owner-lookup-usage = Benutzung: <b>/lookup 12345678</b> oder <b>/lookup @username</b>
+owner-lookup-none = Kein registrierter Nutzer { $query }.
+owner-lookup =
+    { $id } ist { $account } bei { $service }
+    Profillinks: { $profile }, Albumcover: { $cover }, Meilensteine: { $milestones }, Ankündigungen: { $broadcasts }
+    Zeitzone: { $timezone }, Zahlen: { $locale }, Sprache: { $language }
+    Persönlichkeit: { $personality }
+    Letzter Befehl: { $last_seen }
+owner-never = nie
+owner-reloaded = { $count } Kataloge und die Befehlslisten neu geladen.
+owner-reload-failed = Neu laden fehlgeschlagen: { $error }
+owner-cache = Der HTTP-Cache hält { $entries } von { $capacity } Antworten für je { $ttl } Sekunden. <b>/cache flush</b> leert ihn.
+owner-broadcast-usage = Benutzung: <b>/broadcast Text</b>, HTML geht. Die Nachricht geht an alle, die Ankündigungen nicht abgeschaltet haben.
+owner-broadcast-progress = Sende... { $sent } gesendet, { $failed } fehlgeschlagen, { $total } insgesamt.
+owner-broadcast-done = Fertig gesendet, { $sent } gesendet, { $failed } fehlgeschlagen, { $total } insgesamt.
+broadcast-footer = <i>Diese Ankündigungen kannst du in /preferences abschalten.</i>
+owner-upstream = { $icon } { $name }, { $latency } ms
+owner-upstream-error = {"    "}letzter Fehler { $ago }: { $error }
+owner-upstream-breaker = {"    "}Befehle brechen noch { $seconds } s sofort ab
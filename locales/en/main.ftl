@@ -0,0 +1,373 @@
+## general
+
+bot-started = Bot started.
+err-msg = Something went wrong, please try again later.
+not-registered = You are not registered yet. Use /set [username] to link your account.
+not-registered-inline = Link your account
+welcome-text =
+    Welcome to LastFM Robot. Use /set [username] to set your username.
+
+    Check out <a href="https://kawaiidango.github.io/pano-scrobbler">Pano Scrobbler</a> to set up scrobbling on Windows, Linux or Android
+
+    Some commands work without a /
+privacy-stored =
+    The bot, LastFM Robot stores a mapping of the user's Telegram ID and Telegram username,
+    to their scrobbling service (Lastfm, Librefm or ListenBrainz) username, the user's bot preferences and when they last used a command.
+
+    This information is used to fetch and display the user's scrobble information.
+privacy-groups = In groups, it remembers which registered users used it there, for /tastematrix and @mentions, and the settings the group's admins picked.
+privacy-analytics = Each command and button press is counted with its name, the time, the user's Telegram language and an ID hashed from their Telegram ID with a secret salt, for overall analytics.
+privacy-amplitude = These counts are also sent to Amplitude (amplitude.com).
+privacy-opt-out = The user can turn this off with /privacy off, or under Usage analytics in /preferences.
+privacy-opted-out = The user turned this off, so nothing is counted for them. /privacy on turns it back on.
+privacy-delete = The user may choose to delete this information and unlink themselves from the bot, by clicking on Unlink on the /preferences command.
+anon-kun = Please send commands from your own account, not anonymously.
+bots-music = Bots don't listen to music.
+its-me = That's me.
+no = Not allowed.
+loading = Loading...
+not-found = Not found.
+user-not-found = No such user
+private-profile = Your scrobbles are hidden. To use this bot, disable that at https://www.last.fm/settings/privacy
+lastfm-down = Last.fm seems to be down right now, please try again in a minute.
+librefm-down = Libre.fm seems to be down right now, please try again in a minute.
+listenbrainz-down = ListenBrainz seems to be down right now, please try again in a minute.
+no-scrobbles = No scrobbles found.
+message-unmodified = No updates from your profile
+button-outdated = This button is outdated, run the command again
+message-too-old = This message is too old and can't be edited
+group-only = This only works in groups.
+admins-only = Only group admins can do this.
+and = {" "}and{" "}
+
+## commands
+
+help-header = These commands are supported:
+cmd-status = Your last played song
+cmd-status_full = Your last 3 songs and album art
+cmd-loved = Your last 5 loved tracks
+cmd-collage = Create album collage
+cmd-compat = Your compatibility score
+cmd-tastematrix = Compatibility of everyone in this group
+cmd-random = A random top artist/album/track
+cmd-topkek = Top artists/albums/tracks as text
+cmd-flex = Flex your numbers
+cmd-milestones = Milestone announcements in this group
+cmd-personality = How I talk in this group
+cmd-chatsettings = Group settings for admins
+cmd-clock = When you listen to music
+cmd-genres = Your top genres
+cmd-wrapped = Your year in review
+cmd-plays = Your plays of a track, its album and artist
+cmd-artist = Stats for an artist
+cmd-album = Stats for an album
+cmd-track = Stats for a track
+cmd-preferences = Your preferences for this bot
+cmd-help = Help
+cmd-privacy = Privacy policy
+
+collage-usage = Direct usage: <b>collage 3 1m, /collage clean 4 alltime, /collage 5 1y below ranks gaps rounded light header, /collage 4 1m rainbow, /collage 7 alltime png file</b> etc.
+top-usage = Direct usage: <b>/topkek artists 1m , /topkek tracks alltime, /topkek albums 1y chart, /topkek tracks 1m 25</b>
+random-usage = Direct usage: <b>/random artists 1m , /random tracks alltime</b>
+entity-usage =
+    Direct usage: <b>/track artist - title, /album artist - album, /artist artist</b>
+
+    Without any of those, it uses what you are listening to.
+wrapped-usage = Usage: <b>/wrapped 2024</b>, or just /wrapped for this year so far.
+
+## inline mode
+
+inline-status = Status
+inline-status-text = Status
+inline-status_full = Expanded Status
+inline-status_full-text = Expanded status
+inline-loved = Loved
+inline-loved-text = Loved
+inline-flex = Flex your numbers
+inline-flex-text = Flex your numbers
+inline-topkek = Top artists/albums/tracks as text
+inline-topkek-text = Top artists/albums/tracks as text
+inline-random = Shuffle your scrobbles
+inline-random-text = Shuffle your scrobbles
+inline-collage = Album art collage
+inline-collage-text = Album art collage
+inline-compat = Compatibility with { $target }
+inline-compat-text = Compatibility
+
+## shared pieces
+
+count-plays = { $count } plays
+count-artists = { $count } artists
+count-scrobbles = { $count } scrobbles
+
+period-one-week = 1 week
+period-one-month = 1 month
+period-three-months = 3 months
+period-six-months = 6 months
+period-one-year = 1 year
+period-all-time = All time
+
+entry-artist = artist
+entry-album = album
+entry-track = track
+entries-artist = artists
+entries-album = albums
+entries-track = tracks
+button-artist = Artist
+button-album = Album
+button-track = Track
+button-alltime = alltime
+
+choose-type = Choose type:
+choose-period = Choose time period:
+choose-size = Choose a size:
+
+weekday-0 = Monday
+weekday-1 = Tuesday
+weekday-2 = Wednesday
+weekday-3 = Thursday
+weekday-4 = Friday
+weekday-5 = Saturday
+weekday-6 = Sunday
+weekday-short-0 = Mon
+weekday-short-1 = Tue
+weekday-short-2 = Wed
+weekday-short-3 = Thu
+weekday-short-4 = Fri
+weekday-short-5 = Sat
+weekday-short-6 = Sun
+
+month-1 = January
+month-2 = February
+month-3 = March
+month-4 = April
+month-5 = May
+month-6 = June
+month-7 = July
+month-8 = August
+month-9 = September
+month-10 = October
+month-11 = November
+month-12 = December
+month-short-1 = Jan
+month-short-2 = Feb
+month-short-3 = Mar
+month-short-4 = Apr
+month-short-5 = May
+month-short-6 = Jun
+month-short-7 = Jul
+month-short-8 = Aug
+month-short-9 = Sep
+month-short-10 = Oct
+month-short-11 = Nov
+month-short-12 = Dec
+date-short = { $month } { $day }
+
+## status and loved
+
+status-now-listening = { $name } is now listening to
+status-was-listening = { $name } was listening to
+status-plays = , { $plays } plays
+status-loved = , 💗 loved
+card-now = now
+loved-heading = { $name }'s loved tracks:
+info-lastfm =
+    { $plays } plays
+    { $listeners } 🌎 listeners
+    { $scrobbles } 🌎 scrobbles
+info-listenbrainz =
+    { $listeners } 🌎 listeners
+    { $listens } 🌎 listens
+info-track-failed = Failed to fetch track info
+
+## set and preferences
+
+set-click =
+    usage: <b>/set username</b> to set your username for lastfm
+    <b>/set username listenbrainz</b> to set your username for listenbrainz
+set-done =
+    ✅Username set for { $service }!
+
+    Use /preferences to show links to your { $service } profile, or always show album art for status if available.
+
+    Not { $service }? Change your account type using the buttons.
+set-failed =
+    { $error }
+
+    { user-not-found } for { $service }
+
+    Change your account type using the buttons.
+unset = Your username has been unlinked from the bot.
+pref-profile-links = Profile links
+pref-album-art = Always show album art
+pref-milestones = Milestone notifications
+pref-broadcasts = Announcements from the bot owner
+pref-analytics = Usage analytics
+pref-unlink = Unlink your account
+pref-personality = Personality: { $personality }
+timezone-usage = Change it with <b>/preferences timezone Europe/Berlin</b>, any IANA time zone name works.
+locale-usage = Change how numbers look with <b>/preferences locale de</b>, like en, de, fr or en-IN.
+language-usage = Change my language with <b>/preferences language de</b>, or <b>auto</b> to follow your Telegram app. I speak { $languages }.
+settings =
+    Settings for { $name }
+
+    Time zone: { $timezone }
+    { timezone-usage }
+
+    Numbers: { $numbers } ({ $locale })
+    { locale-usage }
+
+    Language: { $language }
+    { language-usage }
+
+    Personality: { $personality }
+    { personality-usage }
+
+## personalities
+
+personality-uwu = uwu
+personality-plain = plain
+personality-default = everyone's own choice
+personality-usage = Change how I talk with <b>/preferences personality plain</b>, or <b>auto</b> for the default. Pick from { $personalities }.
+personality-private = Change how I talk to you in /preferences. Admins can use <b>/personality plain</b> in a group to pick for everyone there, choose from { $personalities }.
+personality-chat-usage = I talk like this here: { $personality }. Admins can change it with <b>/personality plain</b>, or <b>/personality default</b> to let everyone choose. Pick from { $personalities }.
+personality-chat-set = This group now gets the { $personality } personality.
+personality-chat-default = Everyone here gets the personality they picked in /preferences now.
+
+## chat settings
+
+chatsettings =
+    Settings for this group, only admins can change them.
+
+    Commands without a /: { $slashless }
+    Clean collages: { $clean }
+    Default period: { $period }
+    Topics: { $topics }
+    Turned off: { $disabled }
+
+    { chatsettings-usage }
+chatsettings-usage = Use the buttons, or type things like <b>/chatsettings disable collage</b>, <b>/chatsettings period 1m</b> (1w, 1m, 3m, 6m, 1y, alltime or default), <b>/chatsettings clean on</b> or <b>/chatsettings slashless off</b>. In a forum, <b>/chatsettings topic</b> limits me to the topics it was sent in.
+chatsettings-on = on
+chatsettings-off = off
+chatsettings-none = nothing
+chatsettings-topics-all = all of them
+chatsettings-topics-some = limited to { $count }
+chatsettings-period-none = ask every time
+chatsettings-slashless = Commands without a /
+chatsettings-clean = Clean collages
+chatsettings-period = Period: { $period }
+chatsettings-topic = Works in this topic
+
+## top, collage, random
+
+top-heading = { $name }'s top { $entries } for { $period }
+top-line = { $entry } -> { $plays } plays
+collage-heading = { $name }'s { $period } album collage
+collage-librefm = Collages aren't available for Librefm.
+random-heading = { $name }'s random { $entry } for { $period }
+random-entry =
+    { $entry }
+    ({ $plays } plays)
+
+## flex and milestones
+
+flex-counts =
+    { $artists } artists
+    { $albums } albums
+    { $tracks } tracks
+    { $plays } plays
+flex-streak = { $days } day streak
+flex-next = { $plays } plays to { $milestone }
+flex-since = Since { $date }
+milestone-announcement = 🎉 { $name } just hit { $reached }!
+milestone-notification = 🎉 You just hit { $reached }!
+milestones-on = I'll celebrate everyone's scrobble milestones here. They need to turn on milestone notifications in /preferences.
+milestones-off = No more milestone announcements in this group.
+milestones-private = Turn on milestone notifications in /preferences. Admins can use /milestones in a group to have them announced there.
+
+## clock, wrapped, genres
+
+clock-heading = { $name }'s listening clock for { $period }
+clock-summary =
+    { $scrobbles } scrobbles
+    Busiest hour: { $hour } ({ $hour_scrobbles } scrobbles)
+    Busiest day: { $day }
+    Time zone: { $timezone }
+clock-summary-partial =
+    { $scrobbles } scrobbles (only the latest ones were counted)
+    Busiest hour: { $hour } ({ $hour_scrobbles } scrobbles)
+    Busiest day: { $day }
+    Time zone: { $timezone }
+wrapped-heading = { $name }'s { $year } wrapped
+wrapped-total = <b>{ $scrobbles }</b> scrobbles
+wrapped-total-partial = <b>{ $scrobbles }</b> scrobbles (only the latest ones were counted)
+wrapped-scrobbles = scrobbles
+wrapped-top-artists = Top artists
+wrapped-top-albums = Top albums
+wrapped-top-tracks = Top tracks
+wrapped-new-artists = New artists: { $count }
+wrapped-new-artists-like = { $count } new artists, like
+wrapped-busiest-month = Busiest month: { $month } ({ $scrobbles })
+wrapped-busiest-day = Busiest day: { $day } ({ $scrobbles })
+wrapped-longest-streak = Longest streak: { $days } days
+wrapped-longest-streak-range = Longest streak: { $days } days, { $start } to { $end }
+genres-heading = { $name }'s top genres for { $period }
+genres-other = other
+no-genres = No genres found for your top artists.
+
+## track, album, artist
+
+entity-plays-share = { $plays } plays ({ $share }% of all scrobbles)
+entity-global = { $listeners } 🌎 listeners, { $scrobbles } 🌎 scrobbles
+entity-scrobble-dates = First scrobbled on { $first }, last on { $last }
+entity-plays-heading = { $name }'s plays
+
+## compat and tastematrix
+
+compat-click = Usage: compat 1y. Reply to someone's message in a group with this command, or use <b>/compat @username 1y</b> or <b>/compat lastfm:username</b>. In inline mode, type <b>compat @username</b> after my name.
+they-not-registered = They need to /set their username with me.
+mention-not-registered = I don't know them yet, they need to /set their username with me and use a command.
+compat-nothing-common = No common artists in { $period }
+compat-result =
+    { $name1 } and { $name2 } both listen to
+    { $artists }
+
+    Compatibility score is { $score }%, based on { $period }
+    Artists { $artists_score }, albums { $albums_score }, tracks { $tracks_score }
+tastematrix-heading = Taste matrix for { $period }
+tastematrix-line = { $name }: most like { $most }, least like { $least }
+tastematrix-few-members = I need at least two members here who have /set their username and used me in this group.
+
+## owner
+
+owner-stats =
+    Registered users: { $users }
+    { $api_types }
+
+    Active in the last day: { $day }, week: { $week }, month: { $month }
+
+    Events since { $since } UTC, <b>/stats 7</b> for a week:
+    { $commands }
+
+    Per day:
+    { $daily }
+owner-stats-day = { $date }: { $events } events from { $users } users
+owner-lookup-usage = Usage: <b>/lookup 12345678</b> or <b>/lookup @username</b>
+owner-lookup-none = No registered user { $query }.
+owner-lookup =
+    { $id } is { $account } on { $service }
+    Profile links: { $profile }, album art: { $cover }, milestones: { $milestones }, announcements: { $broadcasts }
+    Time zone: { $timezone }, numbers: { $locale }, language: { $language }
+    Personality: { $personality }
+    Last command: { $last_seen }
+owner-never = never
+owner-reloaded = Reloaded { $count } catalogues and the command lists.
+owner-reload-failed = Reloading failed: { $error }
+owner-cache = The HTTP cache holds { $entries } of { $capacity } responses for { $ttl } seconds each. <b>/cache flush</b> empties it.
+owner-broadcast-usage = Usage: <b>/broadcast text</b>, HTML works. It goes to everyone who didn't turn announcements off.
+owner-broadcast-progress = Broadcasting... { $sent } sent, { $failed } failed, { $total } in total.
+owner-broadcast-done = Broadcast finished, { $sent } sent, { $failed } failed, { $total } in total.
+broadcast-footer = <i>Turn these announcements off in /preferences.</i>
+owner-upstream = { $icon } { $name }, { $latency } ms
+owner-upstream-error = {"    "}last error { $ago }: { $error }
+owner-upstream-breaker = {"    "}commands fail fast for another { $seconds } s
@@ -0,0 +1,44 @@
+# the uwu voice, anything not here comes from ../main.ftl
+
+welcome-text =
+    Welcome to LastFM Robot. Use /set [username] to set your uwusername.
+
+    Check out <a href="https://kawaiidango.github.io/pano-scrobbler">Pano Scrobbler</a> to set up scrobbling on Windows, Linux or Android
+
+    Some commands work without a /
+bot-started = I just woke up from the dead.
+err-msg = Oopsie doopsie, I did a fucky wucky!
+not-registered = You are not registered yet. Use /set [username] to link your uwusername.
+anon-kun = Hieee anon kun
+bots-music = We bots don't listen to music, baaaaaka.
+its-me = Lookie, its me!!!
+no = Nuuuuuuuuuu!
+loading = lOwOding...
+user-not-found = No such uwuser
+no-scrobbles = No scrwobbles fownd!
+group-only = This only wowks in gwoups!
+admins-only = Onwy gwoup admins can do this!
+cmd-tastematrix = Compatibiwity of evewyone in this gwoup
+cmd-flex = Flewx your nuwmbers
+cmd-milestones = Miwestone announcements in this gwoup
+cmd-clock = When you wisten to music
+cmd-genres = Youw top genwes
+cmd-wrapped = Youw yeaw in weview
+cmd-plays = Your pways of a track, its album and artist
+cmd-preferences = Your pwefewences for this bot
+cmd-help = Weeeeelp!
+cmd-privacy = Priwacy powicy
+inline-status-text = Staaaaaaatus
+inline-status_full-text = Expanded Staaaaatus
+unset = Your uwusername has been unlinked from the bot.
+no-genres = No genwes fownd for your top artists!
+milestones-on = I'll cewebwate evewyone's scwobble miwestones hewe! They need to turn on miwestone notifications in /preferences.
+milestones-off = No mowe miwestone announcements in this gwoup.
+milestones-private = Turn on miwestone notifications in /preferences. Admins can use /milestones in a gwoup to have them announced there.
+they-not-registered = They need to /set their uwusername with me.
+mention-not-registered = I don't know them yet, they need to /set their uwusername with me and use a command.
+tastematrix-few-members = I need at least two members here who have /set their uwusername and used me in this gwoup.
+
+cmd-personality = How I tawk in this gwoup
+cmd-chatsettings = Gwoup settings fow admins
+personality-chat-set = Evewyone hewe gets the { $personality } me now!
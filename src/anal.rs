@@ -1,14 +1,16 @@
 // from https://github.com/arsenron/amplitude
 
 use std::{
+    error::Error,
     sync::{LazyLock, Mutex},
-    time::{SystemTime, UNIX_EPOCH},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
-use crate::{api_requester::CLIENT_NOCACHE, config};
+use crate::{DB, api_requester::CLIENT_NOCACHE, config};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct UploadBody {
@@ -27,29 +29,131 @@ struct Event {
     platform: Option<String>,
     time: Option<u64>,
     language: Option<String>,
-    ip: Option<String>,
 }
 
-const MAX_EVENTS_TO_TRIGGER_SEND: usize = 50; // todo increase later
+// somewhere buffered events end up
+#[async_trait::async_trait]
+trait Sink: Send + Sync {
+    fn name(&self) -> &'static str;
+    async fn send(&self, events: &[Event]) -> Result<(), Box<dyn Error + Send + Sync>>;
+}
+
+// the events table in users.sqlite, what /stats counts from
+struct LocalSink;
+
+#[async_trait::async_trait]
+impl Sink for LocalSink {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    async fn send(&self, events: &[Event]) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let db = DB.lock().unwrap();
+        for event in events {
+            db.insert_event(
+                event.time.unwrap_or_default(),
+                event.event_type.as_deref().unwrap_or_default(),
+                event.user_id.as_deref(),
+                event.language.as_deref(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+struct AmplitudeSink;
+
+#[async_trait::async_trait]
+impl Sink for AmplitudeSink {
+    fn name(&self) -> &'static str {
+        "amplitude"
+    }
+
+    /// Sends bunch of events to the amplitude servers
+    async fn send(&self, events: &[Event]) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let upload_body = UploadBody {
+            api_key: config::AMPLITUDE_KEY.into(),
+            events: events.to_vec(),
+        };
+        let response = CLIENT_NOCACHE
+            .post(URL_BATCH)
+            .json(&upload_body)
+            .send()
+            .await?;
+        let status = response.status();
+        let text = response.text().await.unwrap_or(DEFAULT_SERVER_ERROR.into());
+
+        match status {
+            StatusCode::OK => Ok(()),
+            _ => Err(Box::from(text)),
+        }
+    }
+}
+
+const MAX_EVENTS_TO_TRIGGER_SEND: usize = 50;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
 const URL_BATCH: &str = "https://api2.amplitude.com/batch";
 const DEFAULT_SERVER_ERROR: &str = r#"{"error": "Some kind of server error"}"#;
 static EVENTS_BUFFER: LazyLock<Mutex<Vec<Event>>> = LazyLock::new(|| Mutex::new(Vec::new()));
+// amplitude only gets events when there is a key for it
+static SINKS: LazyLock<Vec<Box<dyn Sink>>> = LazyLock::new(|| {
+    let mut sinks: Vec<Box<dyn Sink>> = vec![Box::new(LocalSink)];
+    if amplitude_enabled() {
+        sinks.push(Box::new(AmplitudeSink));
+    }
+    sinks
+});
+
+// a salt generated on the first start is kept in users.sqlite when the config has none
+static SALT: LazyLock<String> = LazyLock::new(|| {
+    if !config::ANALYTICS_SALT.is_empty() {
+        return config::ANALYTICS_SALT.to_string();
+    }
+    let generated = format!(
+        "{:032x}{:032x}",
+        rand::random::<u128>(),
+        rand::random::<u128>()
+    );
+    DB.lock()
+        .unwrap()
+        .fetch_or_insert_meta("analytics_salt", &generated)
+        .unwrap_or_else(|e| {
+            log::error!("analytics salt can't be stored, ids will change on restart {e}");
+            generated
+        })
+});
+
+pub fn amplitude_enabled() -> bool {
+    !config::AMPLITUDE_KEY.is_empty()
+}
+
+// the same user always gets the same id, which can't be turned back into theirs without the salt
+fn pseudonymous_id(tg_user_id: u64) -> String {
+    let digest = Sha256::new()
+        .chain_update(SALT.as_bytes())
+        .chain_update(tg_user_id.to_le_bytes())
+        .finalize();
+    digest[..16].iter().map(|x| format!("{x:02x}")).collect()
+}
 
 pub async fn add_event(
     event_type: &str,
     user: Option<&teloxide::types::User>,
     bot_username: String,
-) -> Result<(), reqwest_middleware::Error> {
-    let user_id = user
-        .cloned()
-        .map(|x| x.id.0.to_string())
-        .unwrap_or_default();
-    let language_code = user.cloned().map(|x| x.language_code).unwrap_or_default();
-
-    if let Ok(mut buffer) = EVENTS_BUFFER.lock() {
+) {
+    let opted_out = user.is_some_and(|x| DB.lock().unwrap().analytics_opted_out(x.id.0));
+    if opted_out {
+        return;
+    }
+
+    let user_id = user.map(|x| pseudonymous_id(x.id.0));
+    let language_code = user.and_then(|x| x.language_code.clone());
+
+    let len = {
+        let mut buffer = EVENTS_BUFFER.lock().unwrap();
         buffer.push(Event {
             event_type: event_type.to_string().into(),
-            user_id: user_id.into(),
+            user_id,
             platform: bot_username.into(),
             time: Some(
                 SystemTime::now()
@@ -58,45 +162,38 @@ pub async fn add_event(
                     .as_millis() as u64,
             ),
             language: language_code,
-            ip: "$remote".to_string().into(),
         });
-    }
-
-    if EVENTS_BUFFER.lock().unwrap().len() > MAX_EVENTS_TO_TRIGGER_SEND {
-        send().await?;
+        buffer.len()
+    };
 
-        if let Ok(mut buffer) = EVENTS_BUFFER.lock() {
-            buffer.clear();
-        }
+    if len > MAX_EVENTS_TO_TRIGGER_SEND {
+        flush().await;
     }
-    Ok(())
 }
 
-/// Sends bunch of events to the amplitude servers
-pub async fn send() -> Result<(), reqwest_middleware::Error> {
-    let upload_body = UploadBody {
-        api_key: config::AMPLITUDE_KEY.into(),
-        events: EVENTS_BUFFER.lock().unwrap().clone(),
-    };
-    _send(&upload_body).await?;
-    Ok(())
-}
+// a failing sink loses its copy of the batch, the others still get theirs
+pub async fn flush() {
+    let events = std::mem::take(&mut *EVENTS_BUFFER.lock().unwrap());
+    if events.is_empty() {
+        return;
+    }
 
-async fn _send(upload_body: &UploadBody) -> Result<(), reqwest_middleware::Error> {
-    let response = CLIENT_NOCACHE
-        .post(URL_BATCH)
-        .json(upload_body)
-        .send()
-        .await?;
-    let status = response.status();
-    let text = response.text().await.unwrap_or(DEFAULT_SERVER_ERROR.into());
-
-    match status {
-        StatusCode::OK => {}
-        _ => {
-            log::error!("{text}");
+    for sink in SINKS.iter() {
+        if let Err(e) = sink.send(&events).await {
+            log::error!(
+                "{} analytics sink failed for {} events {e}",
+                sink.name(),
+                events.len()
+            );
         }
     }
+}
 
-    Ok(())
+// quiet hours would otherwise keep a few events buffered forever
+pub async fn run_flusher() {
+    let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+    loop {
+        interval.tick().await;
+        flush().await;
+    }
 }
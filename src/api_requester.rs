@@ -1,13 +1,18 @@
-use std::{error::Error, sync::LazyLock, time::Duration};
+use std::{
+    error::Error,
+    sync::{Arc, LazyLock},
+    time::{Duration, Instant},
+};
 
 use http::Extensions;
 use http_cache_reqwest::{Cache, CacheMode, CacheOptions, HttpCache, MokaManager};
 use reqwest::{Request, Response, StatusCode, Url, header::HeaderValue};
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware, Middleware, Next};
 use serde_json::Value;
-use strum_macros::{Display, EnumString, IntoStaticStr};
+use strum_macros::{Display, EnumString, FromRepr, IntoStaticStr};
+use tokio::task;
 
-use crate::{config, consts};
+use crate::{config, health, metrics};
 
 #[derive(Debug)]
 pub struct Track {
@@ -56,15 +61,17 @@ pub struct ScrobbleUser {
     pub registered_date: Option<u64>,
 }
 
-#[derive(Debug, PartialEq, EnumString, Display, IntoStaticStr)]
+#[derive(Debug, Clone, Copy, PartialEq, EnumString, Display, IntoStaticStr, FromRepr)]
 #[strum(serialize_all = "snake_case")]
+#[repr(u8)]
 pub enum ApiType {
     Lastfm,
     Librefm,
     Listenbrainz,
 }
 
-#[derive(Debug, PartialEq, EnumString, Display, IntoStaticStr)]
+#[derive(Debug, Clone, Copy, PartialEq, EnumString, Display, IntoStaticStr, FromRepr)]
+#[repr(u8)]
 pub enum TimePeriod {
     #[strum(serialize = "1 week")]
     OneWeek,
@@ -80,8 +87,9 @@ pub enum TimePeriod {
     AllTime,
 }
 
-#[derive(Debug, PartialEq, EnumString, Display, IntoStaticStr)]
+#[derive(Debug, Clone, Copy, PartialEq, EnumString, Display, IntoStaticStr, FromRepr)]
 #[strum(serialize_all = "snake_case")]
+#[repr(u8)]
 pub enum EntryType {
     Artist,
     Album,
@@ -124,6 +132,100 @@ impl Middleware for ForceCacheMiddleware {
     }
 }
 
+// times requests per service, cache hits never reach one.
+// sits inside Response200Middleware, so a 404 is still an answer and not an upstream error
+struct MetricsMiddleware {
+    cached: bool,
+}
+
+#[async_trait::async_trait]
+impl Middleware for MetricsMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        let upstream = upstream_name(req.url());
+        let started = Instant::now();
+        let result = next.run(req, extensions).await;
+
+        // nothing was sent while the breaker is open
+        if result
+            .as_ref()
+            .is_err_and(|e| health::upstream_down(e).is_some())
+        {
+            return result;
+        }
+
+        // set by the cache layer
+        let cache_hit = result
+            .as_ref()
+            .is_ok_and(|x| x.headers().get("x-cache").is_some_and(|x| x == "HIT"));
+        if self.cached {
+            metrics::inc(
+                "http_cache_requests_total",
+                &[("result", if cache_hit { "hit" } else { "miss" })],
+            );
+        }
+        if !cache_hit {
+            let labels = [("upstream", upstream.as_str())];
+            metrics::inc("upstream_requests_total", &labels);
+            metrics::observe("upstream_seconds", &labels, started.elapsed());
+            let failed = match &result {
+                Ok(resp) => resp.status().is_server_error(),
+                Err(_) => true,
+            };
+            if failed {
+                metrics::inc("upstream_errors_total", &labels);
+            }
+        }
+        result
+    }
+}
+
+fn upstream_api_type(url: &Url) -> Option<ApiType> {
+    (0..)
+        .map_while(ApiType::from_repr)
+        .find(|x| url.as_str().starts_with(get_base_url(x)))
+}
+
+// one of the scrobbling services, or the host for everything else
+fn upstream_name(url: &Url) -> String {
+    upstream_api_type(url)
+        .map(|x| x.to_string())
+        .unwrap_or_else(|| url.host_str().unwrap_or_default().to_owned())
+}
+
+// innermost, so cached responses are still served while a service is down
+struct CircuitBreakerMiddleware {}
+
+#[async_trait::async_trait]
+impl Middleware for CircuitBreakerMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        let Some(api_type) = upstream_api_type(req.url()) else {
+            return next.run(req, extensions).await;
+        };
+
+        // fail fast instead of making everyone wait for the timeout
+        if health::breaker_open_for(api_type).is_some() {
+            return Err(reqwest_middleware::Error::Middleware(
+                health::UpstreamDown(api_type).into(),
+            ));
+        }
+
+        let result = next.run(req, extensions).await;
+        let ok = result.as_ref().is_ok_and(|x| !x.status().is_server_error());
+        health::record_upstream(api_type, ok);
+        result
+    }
+}
+
 struct Response200Middleware {}
 #[async_trait::async_trait]
 impl Middleware for Response200Middleware {
@@ -137,10 +239,11 @@ impl Middleware for Response200Middleware {
         if resp.status().is_success() {
             Ok(resp)
         } else {
+            // message ids are translated when shown, anything else is passed through
             let display_msg = match resp.status() {
-                StatusCode::NOT_FOUND => consts::USER_NOT_FOUND,
-                StatusCode::FORBIDDEN => consts::PRIVATE_PROFILE,
-                _ => resp.status().canonical_reason().unwrap_or(consts::ERR_MSG),
+                StatusCode::NOT_FOUND => "user-not-found",
+                StatusCode::FORBIDDEN => "private-profile",
+                _ => resp.status().canonical_reason().unwrap_or("err-msg"),
             };
 
             return Err(reqwest_middleware::Error::Middleware(anyhow::anyhow!(
@@ -150,6 +253,19 @@ impl Middleware for Response200Middleware {
     }
 }
 
+// each album track costs up to two requests for its scrobble dates
+const MAX_ALBUM_TRACKS_FOR_DATES: usize = 30;
+// tracks looked up at once, to stay within lastfm's per key rate limit
+const ALBUM_DATES_CONCURRENCY: usize = 4;
+
+// shared with CLIENT's cache manager, so /cache can look inside and flush it
+pub static HTTP_CACHE: LazyLock<moka::future::Cache<String, Arc<Vec<u8>>>> = LazyLock::new(|| {
+    moka::future::Cache::builder()
+        .max_capacity(100)
+        .time_to_live(Duration::from_secs(300))
+        .build()
+});
+
 pub static CLIENT: LazyLock<ClientWithMiddleware> = LazyLock::new(|| {
     ClientBuilder::new(
         reqwest::ClientBuilder::new()
@@ -160,15 +276,11 @@ pub static CLIENT: LazyLock<ClientWithMiddleware> = LazyLock::new(|| {
             .unwrap(),
     )
     .with(Response200Middleware {})
+    .with(MetricsMiddleware { cached: true })
     .with(ForceCacheMiddleware {})
     .with(Cache(HttpCache {
         mode: CacheMode::Default,
-        manager: MokaManager::new(
-            moka::future::Cache::builder()
-                .max_capacity(100)
-                .time_to_live(Duration::from_secs(300))
-                .build(),
-        ),
+        manager: MokaManager::new(HTTP_CACHE.clone()),
         options: http_cache_reqwest::HttpCacheOptions {
             cache_options: CacheOptions {
                 shared: false,
@@ -180,6 +292,7 @@ pub static CLIENT: LazyLock<ClientWithMiddleware> = LazyLock::new(|| {
             ..Default::default()
         },
     }))
+    .with(CircuitBreakerMiddleware {})
     .build()
 });
 
@@ -192,10 +305,11 @@ pub static CLIENT_NOCACHE: LazyLock<ClientWithMiddleware> = LazyLock::new(|| {
             .unwrap(),
     )
     .with(Response200Middleware {})
+    .with(MetricsMiddleware { cached: false })
     .build()
 });
 
-fn get_base_url(api_type: &ApiType) -> &'static str {
+pub fn get_base_url(api_type: &ApiType) -> &'static str {
     match api_type {
         ApiType::Lastfm => "https://ws.audioscrobbler.com/2.0/",
         ApiType::Librefm => "https://libre.fm/2.0/",
@@ -370,12 +484,13 @@ pub async fn fetch_lastfm_album(
     } else {
         0
     };
-    let tags = album_json["tags"]
+    let tags = album_json["tags"]["tag"]
         .as_array()
         .into_iter()
         .flatten()
         .map(|x| x["name"].as_str().unwrap_or_default().to_string())
         .collect::<Vec<_>>();
+    let album_art_url = get_biggest_lastfm_image(&json["album"]);
 
     Ok(Album {
         name,
@@ -383,7 +498,7 @@ pub async fn fetch_lastfm_album(
         listeners,
         playcount,
         user_playcount,
-        album_art_url: None,
+        album_art_url,
         tags: Some(tags),
     })
 }
@@ -432,7 +547,7 @@ pub async fn fetch_lastfm_artist(
     } else {
         0
     };
-    let tags = artist_json["tags"]
+    let tags = artist_json["tags"]["tag"]
         .as_array()
         .into_iter()
         .flatten()
@@ -448,6 +563,257 @@ pub async fn fetch_lastfm_artist(
     })
 }
 
+// one page of a user's scrobbles from a newest first list like user.getTrackScrobbles
+async fn fetch_lastfm_scrobbles_page(
+    params: &[(&str, &str)],
+    page: u64,
+) -> Result<Value, Box<dyn Error + Send + Sync>> {
+    let base_url = get_base_url(&ApiType::Lastfm);
+    let page = page.to_string();
+    let url = Url::parse_with_params(
+        base_url,
+        params.iter().copied().chain([
+            ("page", page.as_str()),
+            ("api_key", config::LASTFM_API_KEY),
+            ("format", "json"),
+        ]),
+    )?;
+    let response = CLIENT.get(url).send().await?;
+    let json = response.json::<serde_json::Value>().await?;
+
+    Ok(json)
+}
+
+// (first, last) scrobble dates, from the first and the last page
+async fn fetch_lastfm_scrobble_dates(
+    params: &[(&str, &str)],
+    root: &str,
+) -> Result<(Option<u64>, Option<u64>), Box<dyn Error + Send + Sync>> {
+    let json = fetch_lastfm_scrobbles_page(params, 1).await?;
+    let tracks = parse_lastfm_tracks(&json[root]["track"])?;
+    let last = tracks.iter().find_map(|x| x.date);
+    let total_pages = json[root]["@attr"]["totalPages"]
+        .as_str()
+        .unwrap_or_default()
+        .parse::<u64>()
+        .unwrap_or_default();
+
+    let first = if total_pages > 1 {
+        let json = fetch_lastfm_scrobbles_page(params, total_pages).await?;
+        parse_lastfm_tracks(&json[root]["track"])?
+            .iter()
+            .rev()
+            .find_map(|x| x.date)
+    } else {
+        tracks.iter().rev().find_map(|x| x.date)
+    };
+
+    Ok((first, last))
+}
+
+pub async fn fetch_lastfm_track_scrobble_dates(
+    username: &str,
+    artist: &str,
+    track: &str,
+) -> Result<(Option<u64>, Option<u64>), Box<dyn Error + Send + Sync>> {
+    fetch_lastfm_scrobble_dates(
+        &[
+            ("method", "user.getTrackScrobbles"),
+            ("track", track),
+            ("artist", artist),
+            ("user", username),
+            ("limit", "1"),
+        ],
+        "trackscrobbles",
+    )
+    .await
+}
+
+pub async fn fetch_lastfm_artist_scrobble_dates(
+    username: &str,
+    artist: &str,
+) -> Result<(Option<u64>, Option<u64>), Box<dyn Error + Send + Sync>> {
+    fetch_lastfm_scrobble_dates(
+        &[
+            ("method", "user.getArtistTracks"),
+            ("artist", artist),
+            ("user", username),
+        ],
+        "artisttracks",
+    )
+    .await
+}
+
+// there is no album scrobble list, so the earliest and latest of its tracks
+pub async fn fetch_lastfm_album_scrobble_dates(
+    username: &str,
+    artist: &str,
+    album: &str,
+) -> Result<(Option<u64>, Option<u64>), Box<dyn Error + Send + Sync>> {
+    let base_url = get_base_url(&ApiType::Lastfm);
+    let url = Url::parse_with_params(
+        base_url,
+        &[
+            ("method", "album.getInfo"),
+            ("album", album),
+            ("artist", artist),
+            ("api_key", config::LASTFM_API_KEY),
+            ("format", "json"),
+        ],
+    )?;
+    let response = CLIENT.get(url).send().await?;
+    let json = response.json::<serde_json::Value>().await?;
+    let tracks_json = &json["album"]["tracks"]["track"];
+    // a single track comes as an object
+    let track_names = match tracks_json {
+        Value::Array(x) => x.iter().collect::<Vec<_>>(),
+        x => vec![x],
+    }
+    .into_iter()
+    .filter_map(|x: &Value| x["name"].as_str().map(|x| x.to_string()))
+    .take(MAX_ALBUM_TRACKS_FOR_DATES)
+    .collect::<Vec<_>>();
+
+    let mut track_names = track_names.into_iter();
+    let mut requests = task::JoinSet::new();
+    let (mut first, mut last): (Option<u64>, Option<u64>) = (None, None);
+    loop {
+        while requests.len() < ALBUM_DATES_CONCURRENCY {
+            let Some(name) = track_names.next() else {
+                break;
+            };
+            let (username, artist) = (username.to_string(), artist.to_string());
+            requests.spawn(async move {
+                fetch_lastfm_track_scrobble_dates(&username, &artist, &name).await
+            });
+        }
+
+        let Some(result) = requests.join_next().await else {
+            break;
+        };
+        // a track that fails just doesn't count
+        let (track_first, track_last) = match result {
+            Ok(Ok(dates)) => dates,
+            Ok(Err(e)) => {
+                log::warn!("failed to fetch album track scrobble dates {e}");
+                continue;
+            }
+            Err(e) => {
+                log::warn!("album track scrobble dates task failed {e}");
+                continue;
+            }
+        };
+        first = match (first, track_first) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        last = last.max(track_last);
+    }
+
+    Ok((first, last))
+}
+
+fn parse_listenbrainz_tags(tags_json: &Value) -> Option<Vec<String>> {
+    let mut tags = tags_json
+        .as_array()?
+        .iter()
+        .filter_map(|x| Some((x["tag"].as_str()?.to_string(), x["count"].as_u64()?)))
+        .collect::<Vec<_>>();
+    tags.sort_by_key(|x| std::cmp::Reverse(x.1));
+    Some(tags.into_iter().map(|x| x.0).collect())
+}
+
+async fn fetch_listenbrainz_popularity(
+    entity: &str,
+    mbid: &str,
+) -> Result<(u64, u64), Box<dyn Error + Send + Sync>> {
+    let url = format!("https://api.listenbrainz.org/1/popularity/{entity}");
+    let body = serde_json::json!({ format!("{entity}_mbids"): [mbid] });
+    let response = CLIENT.post(url).json(&body).send().await?;
+    let json = response.json::<Value>().await?;
+    let popularity = &json[0];
+
+    Ok((
+        popularity["total_user_count"].as_u64().unwrap_or_default(),
+        popularity["total_listen_count"]
+            .as_u64()
+            .unwrap_or_default(),
+    ))
+}
+
+// listenbrainz has no per-user play counts for a single recording, so user_playcount stays 0
+pub async fn fetch_listenbrainz_track(
+    artist: &str,
+    title: &str,
+) -> Result<(Track, Option<Artist>), Box<dyn Error + Send + Sync>> {
+    let url = Url::parse_with_params(
+        "https://api.listenbrainz.org/1/metadata/lookup/",
+        &[
+            ("artist_name", artist),
+            ("recording_name", title),
+            ("metadata", "true"),
+            ("inc", "artist tag release"),
+        ],
+    )?;
+
+    let response = CLIENT.get(url).send().await?;
+    let json = response.json::<Value>().await?;
+
+    let recording_mbid = json["recording_mbid"].as_str();
+    if recording_mbid.is_none() {
+        return Err(Box::from("Track not found."));
+    }
+    let recording_mbid = recording_mbid.unwrap();
+    let metadata = &json["metadata"];
+
+    let (listeners, playcount) = fetch_listenbrainz_popularity("recording", recording_mbid)
+        .await
+        .unwrap_or_default();
+
+    let track = Track {
+        name: json["recording_name"].as_str().unwrap_or(title).to_string(),
+        album: json["release_name"].as_str().map(|s| s.to_string()),
+        artist: json["artist_credit_name"]
+            .as_str()
+            .unwrap_or(artist)
+            .to_string(),
+        album_art_url: json["release_mbid"]
+            .as_str()
+            .map(|mbid| format!("https://coverartarchive.org/release/{mbid}/front-250")),
+        date: None,
+        duration: metadata["recording"]["length"].as_u64().unwrap_or_default(),
+        listeners,
+        playcount,
+        user_playcount: 0,
+        user_loved: false,
+        now_playing: false,
+        tags: parse_listenbrainz_tags(&metadata["tag"]["recording"]),
+    };
+
+    let artist_info = match json["artist_mbids"][0].as_str() {
+        Some(artist_mbid) => {
+            let (listeners, playcount) = fetch_listenbrainz_popularity("artist", artist_mbid)
+                .await
+                .unwrap_or_default();
+            let name = metadata["artist"]["artists"][0]["name"]
+                .as_str()
+                .unwrap_or(&track.artist)
+                .to_string();
+
+            Some(Artist {
+                name,
+                playcount,
+                listeners,
+                user_playcount: 0,
+                tags: parse_listenbrainz_tags(&metadata["tag"]["artist"]),
+            })
+        }
+        None => None,
+    };
+
+    Ok((track, artist_info))
+}
+
 pub fn parse_listenbrainz_tracks(
     json_arr: &Value,
 ) -> Result<Vec<Track>, Box<dyn Error + Send + Sync>> {
@@ -563,6 +929,127 @@ pub fn parse_lastfm_tracks(json_arr: &Value) -> Result<Vec<Track>, Box<dyn Error
     Ok(tracks)
 }
 
+// Every scrobble between from and to (unix seconds), newest first.
+// The bool is false when max_pages ran out before reaching from.
+pub async fn fetch_scrobbles_between(
+    username: &str,
+    api_type: &ApiType,
+    from: u64,
+    to: u64,
+    max_pages: usize,
+) -> Result<(Vec<Track>, bool), Box<dyn Error + Send + Sync>> {
+    let base_url = get_base_url(api_type);
+    let mut all_tracks = vec![];
+
+    match api_type {
+        ApiType::Listenbrainz => {
+            let mut max_ts = to;
+            for _ in 0..max_pages {
+                // listenbrainz only takes one of min_ts and max_ts
+                let url = format!("{base_url}user/{username}/listens?max_ts={max_ts}&count=1000");
+                let response = CLIENT.get(&url).send().await?;
+                let json = response.json::<serde_json::Value>().await?;
+                let tracks = parse_listenbrainz_tracks(&json["payload"]["listens"])?;
+
+                let oldest = tracks.iter().filter_map(|x| x.date).min();
+                all_tracks.extend(
+                    tracks
+                        .into_iter()
+                        .filter(|x| x.date.is_some_and(|date| date >= from)),
+                );
+
+                match oldest {
+                    Some(oldest) if oldest > from => max_ts = oldest,
+                    _ => return Ok((all_tracks, true)),
+                }
+            }
+            Ok((all_tracks, false))
+        }
+
+        ApiType::Librefm | ApiType::Lastfm => {
+            let from_str = from.to_string();
+            let to_str = to.to_string();
+            for page in 1..=max_pages {
+                let url = Url::parse_with_params(
+                    base_url,
+                    &[
+                        ("method", "user.getrecenttracks"),
+                        ("user", username),
+                        ("extended", "1"),
+                        ("from", &from_str),
+                        ("to", &to_str),
+                        ("limit", "200"),
+                        ("page", &page.to_string()),
+                        ("api_key", config::LASTFM_API_KEY),
+                        ("format", "json"),
+                    ],
+                )?;
+                let response = CLIENT.get(url).send().await?;
+                let json = response.json::<serde_json::Value>().await?;
+
+                let tracks = parse_lastfm_tracks(&json["recenttracks"]["track"])?;
+                // the now playing track shows up on every page
+                all_tracks.extend(tracks.into_iter().filter(|x| !x.now_playing));
+
+                let total_pages = json["recenttracks"]["@attr"]["totalPages"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .parse::<usize>()
+                    .unwrap_or_default();
+                if page >= total_pages {
+                    return Ok((all_tracks, true));
+                }
+            }
+            Ok((all_tracks, false))
+        }
+    }
+}
+
+// Artists scrobbled between from and to (unix seconds), lastfm and librefm only
+pub async fn fetch_artists_between(
+    username: &str,
+    api_type: &ApiType,
+    from: u64,
+    to: u64,
+) -> Result<Vec<Artist>, Box<dyn Error + Send + Sync>> {
+    if *api_type == ApiType::Listenbrainz {
+        return Err(Box::from("Not supported for ListenBrainz"));
+    }
+
+    let url = Url::parse_with_params(
+        get_base_url(api_type),
+        &[
+            ("method", "user.getweeklyartistchart"),
+            ("user", username),
+            ("from", &from.to_string()),
+            ("to", &to.to_string()),
+            ("api_key", config::LASTFM_API_KEY),
+            ("format", "json"),
+        ],
+    )?;
+    let response = CLIENT.get(url).send().await?;
+    let json = response.json::<serde_json::Value>().await?;
+
+    let artists = json["weeklyartistchart"]["artist"]
+        .as_array()
+        .ok_or("Invalid JSON format: 'weeklyartistchart.artist' is not an array")?
+        .iter()
+        .map(|artist_json| Artist {
+            name: artist_json["name"].as_str().unwrap_or_default().to_string(),
+            listeners: 0,
+            playcount: 0,
+            user_playcount: artist_json["playcount"]
+                .as_str()
+                .unwrap_or_default()
+                .parse::<u64>()
+                .unwrap_or_default(),
+            tags: None,
+        })
+        .collect::<Vec<_>>();
+
+    Ok(artists)
+}
+
 // Get recent tracks for a given user
 pub async fn fetch_recent_tracks(
     username: &str,
@@ -704,6 +1191,19 @@ fn time_period_to_api_string<'a>(duration: &'a TimePeriod, api_type: &'a ApiType
     }
 }
 
+// None for all time
+pub fn time_period_secs(period: &TimePeriod) -> Option<u64> {
+    let day = 60 * 60 * 24;
+    match period {
+        TimePeriod::OneWeek => Some(day * 7),
+        TimePeriod::OneMonth => Some(day * 30),
+        TimePeriod::ThreeMonths => Some(day * 91),
+        TimePeriod::SixMonths => Some(day * 182),
+        TimePeriod::OneYear => Some(day * 365),
+        TimePeriod::AllTime => None,
+    }
+}
+
 // Get albums for a given user
 pub async fn fetch_albums(
     username: &str,
@@ -0,0 +1,705 @@
+use std::{
+    error::Error,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use sha2::{Digest, Sha256};
+use strum_macros::{Display, EnumString, FromRepr, IntoStaticStr};
+
+use crate::{
+    api_requester::{ApiType, EntryType, TimePeriod},
+    collage::{Captions, CollageStyle, OutputFormat, Theme},
+    db::Db,
+};
+
+// bump whenever the layout of an existing payload changes
+const VERSION: u8 = 3;
+// telegram rejects callback data longer than this
+const MAX_CALLBACK_DATA_LEN: usize = 64;
+// payloads that don't fit are kept in the db and referenced by key
+const STORED_PREFIX: char = '~';
+// buttons older than this say they are outdated
+const STORED_PAYLOAD_TTL: Duration = Duration::from_secs(90 * 24 * 60 * 60);
+// expired payloads are deleted at most this often, not on every stored button
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const NONE_BYTE: u8 = u8::MAX;
+
+static LAST_PRUNE: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString, IntoStaticStr, FromRepr)]
+#[strum(serialize_all = "snake_case")]
+#[repr(u8)]
+pub enum StatusType {
+    Compact,
+    CompactWithCover,
+    Expanded,
+    Card,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString, FromRepr)]
+#[strum(serialize_all = "snake_case")]
+#[repr(u8)]
+pub enum PreferenceAction {
+    ProfileShow,
+    ProfileHide,
+    CoverShow,
+    CoverHide,
+    Unset,
+    MilestonesOn,
+    MilestonesOff,
+    PersonalityUwu,
+    PersonalityPlain,
+    BroadcastsOn,
+    BroadcastsOff,
+    AnalyticsOn,
+    AnalyticsOff,
+}
+
+// what a /chatsettings button or argument changes
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatSettingsAction {
+    ToggleCommand(String),
+    EnableCommand(String),
+    DisableCommand(String),
+    Slashless(bool),
+    Clean(bool),
+    // None asks for a period every time
+    Period(Option<TimePeriod>),
+    ToggleTopic,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, FromRepr)]
+#[repr(u8)]
+enum ChatSettingsTag {
+    ToggleCommand,
+    EnableCommand,
+    DisableCommand,
+    Slashless,
+    Clean,
+    Period,
+    ToggleTopic,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, FromRepr)]
+#[repr(u8)]
+enum Tag {
+    Loading,
+    Status,
+    StatusRefresh,
+    Info,
+    Set,
+    Preferences,
+    Collage,
+    Random,
+    Topkek,
+    ChatSettings,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Payload {
+    Loading,
+    Status(StatusType),
+    StatusRefresh(StatusType),
+    Info {
+        artist: String,
+        title: String,
+    },
+    Set {
+        username: String,
+        api_type: ApiType,
+    },
+    Preferences(PreferenceAction),
+    Collage {
+        period: Option<TimePeriod>,
+        size: Option<u32>,
+        style: CollageStyle,
+    },
+    Random {
+        entry_type: Option<EntryType>,
+        period: Option<TimePeriod>,
+    },
+    Topkek {
+        entry_type: Option<EntryType>,
+        period: Option<TimePeriod>,
+        as_chart: bool,
+        count: usize,
+        page: usize,
+    },
+    ChatSettings(ChatSettingsAction),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Callback {
+    // 0 means everyone is allowed to click
+    pub allowed_user_id: u64,
+    pub payload: Payload,
+}
+
+pub fn period_arg(period: TimePeriod) -> &'static str {
+    match period {
+        TimePeriod::OneWeek => "1w",
+        TimePeriod::OneMonth => "1m",
+        TimePeriod::ThreeMonths => "3m",
+        TimePeriod::SixMonths => "6m",
+        TimePeriod::OneYear => "1y",
+        TimePeriod::AllTime => "alltime",
+    }
+}
+
+impl Payload {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Payload::Loading => "loading",
+            Payload::Status(_) => "status",
+            Payload::StatusRefresh(_) => "status_refresh",
+            Payload::Info { .. } => "info",
+            Payload::Set { .. } => "set",
+            Payload::Preferences(_) => "preferences",
+            Payload::Collage { .. } => "collage",
+            Payload::Random { .. } => "random",
+            Payload::Topkek { .. } => "topkek",
+            Payload::ChatSettings(_) => "chatsettings",
+        }
+    }
+
+    pub fn collage() -> Payload {
+        Payload::Collage {
+            period: None,
+            size: None,
+            style: CollageStyle::default(),
+        }
+    }
+
+    pub fn random() -> Payload {
+        Payload::Random {
+            entry_type: None,
+            period: None,
+        }
+    }
+
+    pub fn topkek(count: usize) -> Payload {
+        Payload::Topkek {
+            entry_type: None,
+            period: None,
+            as_chart: false,
+            count,
+            page: 0,
+        }
+    }
+
+    pub fn with_entry_type(self, entry_type: EntryType) -> Payload {
+        match self {
+            Payload::Random { period, .. } => Payload::Random {
+                entry_type: Some(entry_type),
+                period,
+            },
+            Payload::Topkek {
+                period,
+                as_chart,
+                count,
+                page,
+                ..
+            } => Payload::Topkek {
+                entry_type: Some(entry_type),
+                period,
+                as_chart,
+                count,
+                page,
+            },
+            payload => payload,
+        }
+    }
+
+    pub fn with_period(self, period: TimePeriod) -> Payload {
+        match self {
+            Payload::Collage { size, style, .. } => Payload::Collage {
+                period: Some(period),
+                size,
+                style,
+            },
+            Payload::Random { entry_type, .. } => Payload::Random {
+                entry_type,
+                period: Some(period),
+            },
+            Payload::Topkek {
+                entry_type,
+                as_chart,
+                count,
+                page,
+                ..
+            } => Payload::Topkek {
+                entry_type,
+                period: Some(period),
+                as_chart,
+                count,
+                page,
+            },
+            payload => payload,
+        }
+    }
+
+    pub fn with_size(self, size: u32) -> Payload {
+        match self {
+            Payload::Collage { period, style, .. } => Payload::Collage {
+                period,
+                size: Some(size),
+                style,
+            },
+            payload => payload,
+        }
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            break;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+fn write_str(bytes: &mut Vec<u8>, s: &str) {
+    // nothing we put in buttons comes close to 255 bytes, but never cut a character in half
+    let mut len = s.len().min(u8::MAX as usize);
+    while !s.is_char_boundary(len) {
+        len -= 1;
+    }
+    bytes.push(len as u8);
+    bytes.extend_from_slice(&s.as_bytes()[..len]);
+}
+
+fn write_option(bytes: &mut Vec<u8>, value: Option<u8>) {
+    bytes.push(value.unwrap_or(NONE_BYTE));
+}
+
+fn write_style(bytes: &mut Vec<u8>, style: &CollageStyle) {
+    let flags = [
+        style.ranks,
+        style.gaps,
+        style.rounded,
+        style.header,
+        style.rainbow,
+        style.as_file,
+    ];
+    bytes.push(
+        flags
+            .iter()
+            .enumerate()
+            .fold(0, |acc, (i, x)| acc | (*x as u8) << i),
+    );
+    bytes.push(style.captions as u8);
+    bytes.push(style.theme as u8);
+    write_option(bytes, style.format.map(|x| x as u8));
+    write_option(bytes, style.quality);
+}
+
+fn write_chat_settings(bytes: &mut Vec<u8>, action: &ChatSettingsAction) {
+    match action {
+        ChatSettingsAction::ToggleCommand(command) => {
+            bytes.push(ChatSettingsTag::ToggleCommand as u8);
+            write_str(bytes, command);
+        }
+        ChatSettingsAction::EnableCommand(command) => {
+            bytes.push(ChatSettingsTag::EnableCommand as u8);
+            write_str(bytes, command);
+        }
+        ChatSettingsAction::DisableCommand(command) => {
+            bytes.push(ChatSettingsTag::DisableCommand as u8);
+            write_str(bytes, command);
+        }
+        ChatSettingsAction::Slashless(on) => {
+            bytes.push(ChatSettingsTag::Slashless as u8);
+            bytes.push(*on as u8);
+        }
+        ChatSettingsAction::Clean(on) => {
+            bytes.push(ChatSettingsTag::Clean as u8);
+            bytes.push(*on as u8);
+        }
+        ChatSettingsAction::Period(period) => {
+            bytes.push(ChatSettingsTag::Period as u8);
+            write_option(bytes, period.map(|x| x as u8));
+        }
+        ChatSettingsAction::ToggleTopic => bytes.push(ChatSettingsTag::ToggleTopic as u8),
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> Result<u8, Box<dyn Error + Send + Sync>> {
+        let (first, rest) = self
+            .bytes
+            .split_first()
+            .ok_or("Callback data ended early")?;
+        self.bytes = rest;
+        Ok(*first)
+    }
+
+    fn varint(&mut self) -> Result<u64, Box<dyn Error + Send + Sync>> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(Box::from("Callback varint too long"))
+    }
+
+    fn str(&mut self) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let len = self.byte()? as usize;
+        if self.bytes.len() < len {
+            return Err(Box::from("Callback data ended early"));
+        }
+        let (s, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(String::from_utf8(s.to_vec())?)
+    }
+
+    fn option(&mut self) -> Result<Option<u8>, Box<dyn Error + Send + Sync>> {
+        let byte = self.byte()?;
+        Ok((byte != NONE_BYTE).then_some(byte))
+    }
+
+    fn status_type(&mut self) -> Result<StatusType, Box<dyn Error + Send + Sync>> {
+        StatusType::from_repr(self.byte()?).ok_or_else(|| Box::from("Unknown status type"))
+    }
+
+    fn period(&mut self) -> Result<Option<TimePeriod>, Box<dyn Error + Send + Sync>> {
+        self.option()?
+            .map(|x| TimePeriod::from_repr(x).ok_or_else(|| Box::from("Unknown time period")))
+            .transpose()
+    }
+
+    fn entry_type(&mut self) -> Result<Option<EntryType>, Box<dyn Error + Send + Sync>> {
+        self.option()?
+            .map(|x| EntryType::from_repr(x).ok_or_else(|| Box::from("Unknown entry type")))
+            .transpose()
+    }
+
+    fn style(&mut self) -> Result<CollageStyle, Box<dyn Error + Send + Sync>> {
+        let flags = self.byte()?;
+        let flag = |i: u8| flags & (1 << i) != 0;
+        Ok(CollageStyle {
+            ranks: flag(0),
+            gaps: flag(1),
+            rounded: flag(2),
+            header: flag(3),
+            rainbow: flag(4),
+            as_file: flag(5),
+            captions: Captions::from_repr(self.byte()?).ok_or("Unknown captions")?,
+            theme: Theme::from_repr(self.byte()?).ok_or("Unknown theme")?,
+            format: self
+                .option()?
+                .map(|x| OutputFormat::from_repr(x).ok_or("Unknown format"))
+                .transpose()?,
+            quality: self.option()?,
+        })
+    }
+
+    fn chat_settings(&mut self) -> Result<ChatSettingsAction, Box<dyn Error + Send + Sync>> {
+        let tag = ChatSettingsTag::from_repr(self.byte()?).ok_or("Unknown chat setting")?;
+        Ok(match tag {
+            ChatSettingsTag::ToggleCommand => ChatSettingsAction::ToggleCommand(self.str()?),
+            ChatSettingsTag::EnableCommand => ChatSettingsAction::EnableCommand(self.str()?),
+            ChatSettingsTag::DisableCommand => ChatSettingsAction::DisableCommand(self.str()?),
+            ChatSettingsTag::Slashless => ChatSettingsAction::Slashless(self.byte()? != 0),
+            ChatSettingsTag::Clean => ChatSettingsAction::Clean(self.byte()? != 0),
+            ChatSettingsTag::Period => ChatSettingsAction::Period(self.period()?),
+            ChatSettingsTag::ToggleTopic => ChatSettingsAction::ToggleTopic,
+        })
+    }
+}
+
+impl Callback {
+    pub fn new(allowed_user_id: u64, payload: Payload) -> Callback {
+        Callback {
+            allowed_user_id,
+            payload,
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![VERSION];
+        write_varint(&mut bytes, self.allowed_user_id);
+
+        match &self.payload {
+            Payload::Loading => bytes.push(Tag::Loading as u8),
+            Payload::Status(status_type) => {
+                bytes.push(Tag::Status as u8);
+                bytes.push(*status_type as u8);
+            }
+            Payload::StatusRefresh(status_type) => {
+                bytes.push(Tag::StatusRefresh as u8);
+                bytes.push(*status_type as u8);
+            }
+            Payload::Info { artist, title } => {
+                bytes.push(Tag::Info as u8);
+                write_str(&mut bytes, artist);
+                write_str(&mut bytes, title);
+            }
+            Payload::Set { username, api_type } => {
+                bytes.push(Tag::Set as u8);
+                write_str(&mut bytes, username);
+                bytes.push(*api_type as u8);
+            }
+            Payload::Preferences(action) => {
+                bytes.push(Tag::Preferences as u8);
+                bytes.push(*action as u8);
+            }
+            Payload::Collage {
+                period,
+                size,
+                style,
+            } => {
+                bytes.push(Tag::Collage as u8);
+                write_option(&mut bytes, period.map(|x| x as u8));
+                write_option(&mut bytes, size.map(|x| x as u8));
+                write_style(&mut bytes, style);
+            }
+            Payload::Random { entry_type, period } => {
+                bytes.push(Tag::Random as u8);
+                write_option(&mut bytes, entry_type.map(|x| x as u8));
+                write_option(&mut bytes, period.map(|x| x as u8));
+            }
+            Payload::Topkek {
+                entry_type,
+                period,
+                as_chart,
+                count,
+                page,
+            } => {
+                bytes.push(Tag::Topkek as u8);
+                write_option(&mut bytes, entry_type.map(|x| x as u8));
+                write_option(&mut bytes, period.map(|x| x as u8));
+                bytes.push(*as_chart as u8);
+                write_varint(&mut bytes, *count as u64);
+                write_varint(&mut bytes, *page as u64);
+            }
+            Payload::ChatSettings(action) => {
+                bytes.push(Tag::ChatSettings as u8);
+                write_chat_settings(&mut bytes, action);
+            }
+        }
+
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Callback, Box<dyn Error + Send + Sync>> {
+        let mut reader = Reader { bytes };
+
+        let version = reader.byte()?;
+        if version != VERSION {
+            return Err(Box::from(format!("Unsupported callback version {version}")));
+        }
+
+        let allowed_user_id = reader.varint()?;
+        let tag = Tag::from_repr(reader.byte()?).ok_or("Unknown callback tag")?;
+
+        let payload = match tag {
+            Tag::Loading => Payload::Loading,
+            Tag::Status => Payload::Status(reader.status_type()?),
+            Tag::StatusRefresh => Payload::StatusRefresh(reader.status_type()?),
+            Tag::Info => Payload::Info {
+                artist: reader.str()?,
+                title: reader.str()?,
+            },
+            Tag::Set => Payload::Set {
+                username: reader.str()?,
+                api_type: ApiType::from_repr(reader.byte()?).ok_or("Unknown api type")?,
+            },
+            Tag::Preferences => Payload::Preferences(
+                PreferenceAction::from_repr(reader.byte()?).ok_or("Unknown preference")?,
+            ),
+            Tag::Collage => Payload::Collage {
+                period: reader.period()?,
+                size: reader.option()?.map(|x| x as u32),
+                style: reader.style()?,
+            },
+            Tag::Random => Payload::Random {
+                entry_type: reader.entry_type()?,
+                period: reader.period()?,
+            },
+            Tag::Topkek => Payload::Topkek {
+                entry_type: reader.entry_type()?,
+                period: reader.period()?,
+                as_chart: reader.byte()? != 0,
+                count: reader.varint()? as usize,
+                page: reader.varint()? as usize,
+            },
+            Tag::ChatSettings => Payload::ChatSettings(reader.chat_settings()?),
+        };
+
+        Ok(Callback::new(allowed_user_id, payload))
+    }
+
+    pub fn encode(&self, db: &Db) -> String {
+        let bytes = self.to_bytes();
+        let encoded = URL_SAFE_NO_PAD.encode(&bytes);
+        if encoded.len() <= MAX_CALLBACK_DATA_LEN {
+            return encoded;
+        }
+
+        let key = URL_SAFE_NO_PAD.encode(&Sha256::digest(&bytes)[..8]);
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        if let Err(e) = db.store_callback_payload(&key, &bytes, now) {
+            log::error!("failed to store callback payload {e}");
+        }
+        let last_prune = LAST_PRUNE.load(Ordering::Relaxed);
+        let prune_due = now.saturating_sub(last_prune) >= PRUNE_INTERVAL.as_secs()
+            && LAST_PRUNE
+                .compare_exchange(last_prune, now, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok();
+        let pruned = if prune_due {
+            db.delete_callback_payloads_before(now - STORED_PAYLOAD_TTL.as_secs())
+        } else {
+            Ok(0)
+        };
+        if let Err(e) = pruned {
+            log::error!("failed to prune callback payloads {e}");
+        }
+
+        format!("{STORED_PREFIX}{key}")
+    }
+
+    pub fn decode(data: &str, db: &Db) -> Result<Callback, Box<dyn Error + Send + Sync>> {
+        let bytes = match data.strip_prefix(STORED_PREFIX) {
+            Some(key) => db
+                .fetch_callback_payload(key)
+                .ok_or("Stored callback payload not found")?,
+            None => URL_SAFE_NO_PAD.decode(data)?,
+        };
+
+        Callback::from_bytes(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(payload: Payload) {
+        let callback = Callback::new(123456789, payload);
+        let bytes = callback.to_bytes();
+        assert_eq!(Callback::from_bytes(&bytes).unwrap(), callback);
+    }
+
+    #[test]
+    fn payloads_round_trip() {
+        let style = CollageStyle {
+            captions: Captions::Below,
+            ranks: true,
+            rounded: true,
+            theme: Theme::Light,
+            format: Some(OutputFormat::Webp),
+            quality: Some(85),
+            as_file: true,
+            ..CollageStyle::default()
+        };
+
+        for payload in [
+            Payload::Loading,
+            Payload::Status(StatusType::Expanded),
+            Payload::StatusRefresh(StatusType::Compact),
+            Payload::Info {
+                artist: "Sigur Rós".to_string(),
+                title: "Hoppípolla".to_string(),
+            },
+            Payload::Set {
+                username: "ünïcödé".to_string(),
+                api_type: ApiType::Listenbrainz,
+            },
+            Payload::Preferences(PreferenceAction::AnalyticsOff),
+            Payload::collage(),
+            Payload::collage()
+                .with_period(TimePeriod::OneYear)
+                .with_size(7),
+            Payload::Collage {
+                period: Some(TimePeriod::OneWeek),
+                size: Some(3),
+                style,
+            },
+            Payload::random().with_entry_type(EntryType::Track),
+            Payload::topkek(30)
+                .with_entry_type(EntryType::Artist)
+                .with_period(TimePeriod::AllTime),
+            Payload::Topkek {
+                entry_type: Some(EntryType::Album),
+                period: Some(TimePeriod::SixMonths),
+                as_chart: true,
+                count: 300,
+                page: 200,
+            },
+            Payload::ChatSettings(ChatSettingsAction::ToggleCommand("collage".to_string())),
+            Payload::ChatSettings(ChatSettingsAction::EnableCommand("random".to_string())),
+            Payload::ChatSettings(ChatSettingsAction::DisableCommand("flex".to_string())),
+            Payload::ChatSettings(ChatSettingsAction::Slashless(false)),
+            Payload::ChatSettings(ChatSettingsAction::Clean(true)),
+            Payload::ChatSettings(ChatSettingsAction::Period(None)),
+            Payload::ChatSettings(ChatSettingsAction::Period(Some(TimePeriod::ThreeMonths))),
+            Payload::ChatSettings(ChatSettingsAction::ToggleTopic),
+        ] {
+            round_trip(payload);
+        }
+    }
+
+    #[test]
+    fn short_payloads_fit_in_callback_data() {
+        let callback = Callback::new(u64::MAX, Payload::topkek(30));
+        assert!(URL_SAFE_NO_PAD.encode(callback.to_bytes()).len() <= MAX_CALLBACK_DATA_LEN);
+    }
+
+    #[test]
+    fn long_strings_are_cut_at_a_char_boundary() {
+        let username = "é".repeat(200);
+        let callback = Callback::new(
+            1,
+            Payload::Set {
+                username,
+                api_type: ApiType::Lastfm,
+            },
+        );
+        match Callback::from_bytes(&callback.to_bytes()).unwrap().payload {
+            Payload::Set { username, .. } => assert_eq!(username, "é".repeat(127)),
+            payload => panic!("unexpected {payload:?}"),
+        }
+    }
+
+    #[test]
+    fn long_payloads_are_stored() {
+        let db = Db::open(":memory:");
+        let callback = Callback::new(
+            u64::MAX,
+            Payload::Set {
+                username: "a".repeat(100),
+                api_type: ApiType::Listenbrainz,
+            },
+        );
+        let data = callback.encode(&db);
+        assert!(data.starts_with(STORED_PREFIX));
+        assert!(data.len() <= MAX_CALLBACK_DATA_LEN);
+        assert_eq!(Callback::decode(&data, &db).unwrap(), callback);
+        assert!(Callback::decode(&data, &Db::open(":memory:")).is_err());
+    }
+
+    #[test]
+    fn unknown_versions_and_truncated_data_fail() {
+        let mut bytes = Callback::new(1, Payload::collage()).to_bytes();
+        assert!(Callback::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        bytes[0] = VERSION + 1;
+        assert!(Callback::from_bytes(&bytes).is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use image::imageops::FilterType;
+use image::{DynamicImage, Rgba, RgbaImage};
+use imageproc::drawing::{draw_filled_circle_mut, draw_polygon_mut, draw_text_mut, text_size};
+use imageproc::point::Point;
+use num_format::{Locale, ToFormattedString};
+
+use crate::api_requester::{CLIENT_NOCACHE, Track};
+use crate::collage::{self, FONT, OutputFormat};
+use crate::{tr, utils};
+
+const CARD_WIDTH: u32 = 1000;
+const CARD_HEIGHT: u32 = 360;
+const COVER_PX: u32 = 300;
+const MARGIN_PX: u32 = 30;
+const TEXT_X: u32 = COVER_PX + MARGIN_PX * 2;
+const TEXT_WIDTH: u32 = CARD_WIDTH - TEXT_X - MARGIN_PX;
+
+const PRIMARY_COLOR: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const SECONDARY_COLOR: Rgba<u8> = Rgba([200, 200, 200, 255]);
+const HEART_COLOR: Rgba<u8> = Rgba([255, 64, 129, 255]);
+
+async fn fetch_cover(url: &str) -> Option<DynamicImage> {
+    let bytes = CLIENT_NOCACHE
+        .get(url)
+        .send()
+        .await
+        .ok()?
+        .bytes()
+        .await
+        .ok()?;
+    image::load_from_memory(&bytes).ok()
+}
+
+fn blurred_background(cover: Option<&DynamicImage>) -> RgbaImage {
+    match cover {
+        Some(cover) => {
+            // blurring a tiny version and scaling it up is much cheaper than blurring the full card
+            let small = cover
+                .resize_to_fill(CARD_WIDTH / 10, CARD_HEIGHT / 10, FilterType::Triangle)
+                .to_rgba8();
+            let small = image::imageops::blur(&small, 2.0);
+            let mut background =
+                image::imageops::resize(&small, CARD_WIDTH, CARD_HEIGHT, FilterType::Triangle);
+
+            // darken it so that the text stays readable
+            for pixel in background.pixels_mut() {
+                for channel in 0..3 {
+                    pixel[channel] = (pixel[channel] as f32 * 0.45) as u8;
+                }
+            }
+            background
+        }
+        None => RgbaImage::from_fn(CARD_WIDTH, CARD_HEIGHT, |_, y| {
+            let shade = (40.0 - 30.0 * y as f32 / CARD_HEIGHT as f32) as u8;
+            Rgba([shade, shade, shade + 10, 255])
+        }),
+    }
+}
+
+fn draw_heart(image: &mut RgbaImage, cx: i32, cy: i32, r: i32) {
+    draw_filled_circle_mut(image, (cx - r, cy), r, HEART_COLOR);
+    draw_filled_circle_mut(image, (cx + r, cy), r, HEART_COLOR);
+    draw_polygon_mut(
+        image,
+        &[
+            Point::new(cx - 2 * r, cy),
+            Point::new(cx + 2 * r, cy),
+            Point::new(cx, cy + 2 * r),
+        ],
+        HEART_COLOR,
+    );
+}
+
+pub async fn create_now_playing_card(
+    track: &Track,
+    user_playcount: u64,
+    name: &str,
+    locale: &Locale,
+    lang: &str,
+) -> Result<Vec<u8>, anyhow::Error> {
+    let cover = match &track.album_art_url {
+        Some(url) => fetch_cover(url).await,
+        None => None,
+    };
+
+    let mut card = blurred_background(cover.as_ref());
+
+    let mut cover_tile = match &cover {
+        Some(cover) => cover
+            .resize_to_fill(COVER_PX, COVER_PX, FilterType::Lanczos3)
+            .to_rgba8(),
+        None => RgbaImage::from_pixel(COVER_PX, COVER_PX, Rgba([70, 70, 70, 255])),
+    };
+    collage::round_corners(&mut cover_tile, 16);
+    image::imageops::overlay(&mut card, &cover_tile, MARGIN_PX.into(), MARGIN_PX.into());
+
+    let mut draw_line = |y: u32, font_size: f32, color: Rgba<u8>, text: &str| {
+        draw_text_mut(
+            &mut card,
+            color,
+            TEXT_X as i32,
+            y as i32,
+            font_size,
+            &*FONT,
+            &collage::fit_text(text, font_size, TEXT_WIDTH),
+        );
+    };
+
+    let heading = if track.now_playing {
+        tr!(lang, "status-now-listening", name = name)
+    } else {
+        tr!(lang, "status-was-listening", name = name)
+    };
+
+    draw_line(MARGIN_PX + 6, 26.0, SECONDARY_COLOR, &heading);
+    draw_line(MARGIN_PX + 50, 48.0, PRIMARY_COLOR, &track.name);
+    draw_line(MARGIN_PX + 116, 36.0, PRIMARY_COLOR, &track.artist);
+    if let Some(album) = &track.album {
+        draw_line(MARGIN_PX + 166, 28.0, SECONDARY_COLOR, album);
+    }
+
+    let mut stats = vec![];
+    if user_playcount > 0 {
+        stats.push(tr!(
+            lang,
+            "count-plays",
+            count = user_playcount.to_formatted_string(locale)
+        ));
+    }
+    if track.now_playing {
+        stats.push(tr!(lang, "card-now"));
+    } else if let Some(date) = track.date {
+        stats.push(utils::convert_to_timeago(date));
+    }
+    let stats_text = stats.join("  ·  ");
+    let stats_y = MARGIN_PX + COVER_PX - 36;
+    draw_line(stats_y, 28.0, SECONDARY_COLOR, &stats_text);
+
+    if track.user_loved {
+        let stats_width = if stats_text.is_empty() {
+            0
+        } else {
+            text_size(28.0, &*FONT, &stats_text).0 + 24
+        };
+        draw_heart(
+            &mut card,
+            (TEXT_X + stats_width) as i32 + 12,
+            stats_y as i32 + 12,
+            6,
+        );
+    }
+
+    collage::encode_image(card, OutputFormat::Jpeg, None)
+}
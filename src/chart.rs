@@ -0,0 +1,474 @@
+use image::imageops::FilterType;
+use image::{Rgba, RgbaImage};
+use imageproc::drawing::{
+    draw_filled_rect_mut, draw_line_segment_mut, draw_polygon_mut, draw_text_mut, text_size,
+};
+use imageproc::point::Point;
+use imageproc::rect::Rect;
+use num_format::{Locale, ToFormattedString};
+
+use crate::collage::{self, FONT, OutputFormat};
+
+const CHART_WIDTH: u32 = 1000;
+const HEADER_PX: u32 = 80;
+const ROW_PX: u32 = 90;
+const THUMB_PX: u32 = 70;
+const MARGIN_PX: u32 = 20;
+const RANK_X: u32 = MARGIN_PX;
+const THUMB_X: u32 = 70;
+const TEXT_X: u32 = THUMB_X + THUMB_PX + MARGIN_PX;
+const COUNT_LABEL_PX: u32 = 110;
+const BAR_MAX_WIDTH: u32 = CHART_WIDTH - TEXT_X - MARGIN_PX - COUNT_LABEL_PX;
+
+const BACKGROUND_COLOR: Rgba<u8> = Rgba([24, 24, 24, 255]);
+const PRIMARY_COLOR: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const SECONDARY_COLOR: Rgba<u8> = Rgba([170, 170, 170, 255]);
+const BAR_COLOR: Rgba<u8> = Rgba([213, 16, 7, 255]);
+const PLACEHOLDER_COLOR: Rgba<u8> = Rgba([60, 60, 60, 255]);
+
+pub struct ChartEntry {
+    pub title: String,
+    pub playcount: u64,
+    pub image_url: Option<String>,
+}
+
+fn placeholder_thumb(title: &str) -> RgbaImage {
+    let mut thumb = RgbaImage::from_pixel(THUMB_PX, THUMB_PX, PLACEHOLDER_COLOR);
+    let initial = title
+        .chars()
+        .next()
+        .map(|c| c.to_uppercase().to_string())
+        .unwrap_or_default();
+    let (w, h) = text_size(36.0, &*FONT, &initial);
+    draw_text_mut(
+        &mut thumb,
+        SECONDARY_COLOR,
+        (THUMB_PX.saturating_sub(w) / 2) as i32,
+        (THUMB_PX.saturating_sub(h) / 2) as i32,
+        36.0,
+        &*FONT,
+        &initial,
+    );
+    thumb
+}
+
+pub async fn create_chart(
+    entries: &[ChartEntry],
+    first_rank: usize,
+    heading: &str,
+    locale: &Locale,
+) -> Result<Vec<u8>, anyhow::Error> {
+    let chart_height = HEADER_PX + ROW_PX * entries.len() as u32 + MARGIN_PX;
+    let mut chart = RgbaImage::from_pixel(CHART_WIDTH, chart_height, BACKGROUND_COLOR);
+
+    draw_text_mut(
+        &mut chart,
+        PRIMARY_COLOR,
+        MARGIN_PX as i32,
+        24,
+        34.0,
+        &*FONT,
+        &collage::fit_text(heading, 34.0, CHART_WIDTH - MARGIN_PX * 2),
+    );
+
+    let urls = entries
+        .iter()
+        .filter_map(|x| x.image_url.as_deref())
+        .collect::<Vec<_>>();
+    let mut thumbs_bytes = collage::fetch_images(&urls).await.into_iter();
+
+    let max_playcount = entries
+        .iter()
+        .map(|x| x.playcount)
+        .max()
+        .unwrap_or_default()
+        .max(1);
+
+    for (i, entry) in entries.iter().enumerate() {
+        let row_y = HEADER_PX + ROW_PX * i as u32;
+
+        draw_text_mut(
+            &mut chart,
+            SECONDARY_COLOR,
+            RANK_X as i32,
+            row_y as i32 + 22,
+            30.0,
+            &*FONT,
+            &(first_rank + i).to_string(),
+        );
+
+        let thumb = entry
+            .image_url
+            .as_ref()
+            .and_then(|_| thumbs_bytes.next())
+            .and_then(|bytes| bytes.ok())
+            .and_then(|bytes| image::load_from_memory(&bytes).ok())
+            .map(|img| {
+                img.resize_to_fill(THUMB_PX, THUMB_PX, FilterType::Triangle)
+                    .to_rgba8()
+            })
+            .unwrap_or_else(|| placeholder_thumb(&entry.title));
+        image::imageops::overlay(
+            &mut chart,
+            &thumb,
+            THUMB_X.into(),
+            (row_y + (ROW_PX - THUMB_PX) / 2).into(),
+        );
+
+        draw_text_mut(
+            &mut chart,
+            PRIMARY_COLOR,
+            TEXT_X as i32,
+            row_y as i32 + 10,
+            26.0,
+            &*FONT,
+            &collage::fit_text(&entry.title, 26.0, CHART_WIDTH - TEXT_X - MARGIN_PX),
+        );
+
+        let bar_width = ((BAR_MAX_WIDTH as u64 * entry.playcount / max_playcount) as u32).max(4);
+        draw_filled_rect_mut(
+            &mut chart,
+            Rect::at(TEXT_X as i32, row_y as i32 + 48).of_size(bar_width, 26),
+            BAR_COLOR,
+        );
+
+        draw_text_mut(
+            &mut chart,
+            SECONDARY_COLOR,
+            (TEXT_X + bar_width + 10) as i32,
+            row_y as i32 + 48,
+            24.0,
+            &*FONT,
+            &entry.playcount.to_formatted_string(locale),
+        );
+    }
+
+    collage::encode_image(chart, OutputFormat::Jpeg, None)
+}
+
+const PIE_WIDTH: u32 = 1000;
+const PIE_HEIGHT: u32 = 620;
+const PIE_RADIUS: f32 = 240.0;
+const LEGEND_X: u32 = 580;
+const LEGEND_ROW_PX: u32 = 44;
+
+const PIE_COLORS: [Rgba<u8>; 10] = [
+    Rgba([213, 16, 7, 255]),
+    Rgba([245, 130, 48, 255]),
+    Rgba([255, 204, 0, 255]),
+    Rgba([60, 180, 75, 255]),
+    Rgba([70, 190, 200, 255]),
+    Rgba([0, 110, 220, 255]),
+    Rgba([130, 80, 220, 255]),
+    Rgba([240, 50, 200, 255]),
+    Rgba([170, 110, 40, 255]),
+    Rgba([120, 200, 140, 255]),
+];
+
+// slices are (label, share) with shares adding up to 1
+pub fn create_pie_chart(slices: &[(String, f64)], heading: &str) -> Result<Vec<u8>, anyhow::Error> {
+    let mut chart = RgbaImage::from_pixel(PIE_WIDTH, PIE_HEIGHT, BACKGROUND_COLOR);
+
+    draw_text_mut(
+        &mut chart,
+        PRIMARY_COLOR,
+        MARGIN_PX as i32,
+        24,
+        34.0,
+        &*FONT,
+        &collage::fit_text(heading, 34.0, PIE_WIDTH - MARGIN_PX * 2),
+    );
+
+    let center_x = MARGIN_PX as f32 + PIE_RADIUS + 20.0;
+    let center_y = HEADER_PX as f32 + PIE_RADIUS + 10.0;
+
+    // start at 12 o'clock and go clockwise
+    let mut angle = -std::f64::consts::FRAC_PI_2;
+    for (i, (label, share)) in slices.iter().enumerate() {
+        let color = PIE_COLORS[i % PIE_COLORS.len()];
+        let sweep = share * std::f64::consts::TAU;
+
+        // one point per degree is smooth enough at this size
+        let steps = ((sweep.to_degrees().ceil()) as usize).max(1);
+        let mut points = vec![Point::new(center_x as i32, center_y as i32)];
+        for step in 0..=steps {
+            let a = angle + sweep * step as f64 / steps as f64;
+            points.push(Point::new(
+                (center_x as f64 + PIE_RADIUS as f64 * a.cos()).round() as i32,
+                (center_y as f64 + PIE_RADIUS as f64 * a.sin()).round() as i32,
+            ));
+        }
+        points.dedup();
+        if points.len() > 2 && points.first() != points.last() {
+            draw_polygon_mut(&mut chart, &points, color);
+        }
+        angle += sweep;
+
+        let row_y = HEADER_PX + 20 + LEGEND_ROW_PX * i as u32;
+        if row_y + LEGEND_ROW_PX > PIE_HEIGHT {
+            continue;
+        }
+        draw_filled_rect_mut(
+            &mut chart,
+            Rect::at(LEGEND_X as i32, row_y as i32 + 4).of_size(26, 26),
+            color,
+        );
+        let legend_text = format!("{label}  {:.1}%", share * 100.0);
+        draw_text_mut(
+            &mut chart,
+            PRIMARY_COLOR,
+            (LEGEND_X + 40) as i32,
+            row_y as i32,
+            28.0,
+            &*FONT,
+            &collage::fit_text(&legend_text, 28.0, PIE_WIDTH - LEGEND_X - 40 - MARGIN_PX),
+        );
+    }
+
+    collage::encode_image(chart, OutputFormat::Jpeg, None)
+}
+
+const HEATMAP_LABEL_PX: u32 = 260;
+const HEATMAP_CELL_PX: u32 = 60;
+const HEATMAP_LOW_COLOR: [f32; 3] = [40.0, 40.0, 40.0];
+const HEATMAP_HIGH_COLOR: [f32; 3] = [213.0, 16.0, 7.0];
+
+// scores[i][j] is the 0 to 100 compatibility of names[i] and names[j], None if unknown
+pub fn create_heatmap(
+    names: &[String],
+    scores: &[Vec<Option<u32>>],
+    heading: &str,
+) -> Result<Vec<u8>, anyhow::Error> {
+    let n = names.len() as u32;
+    let grid_x = MARGIN_PX + HEATMAP_LABEL_PX;
+    let grid_y = HEADER_PX + 40;
+    let width = (grid_x + HEATMAP_CELL_PX * n + MARGIN_PX).max(600);
+    let height = grid_y + HEATMAP_CELL_PX * n + MARGIN_PX;
+    let mut chart = RgbaImage::from_pixel(width, height, BACKGROUND_COLOR);
+
+    draw_text_mut(
+        &mut chart,
+        PRIMARY_COLOR,
+        MARGIN_PX as i32,
+        24,
+        34.0,
+        &*FONT,
+        &collage::fit_text(heading, 34.0, width - MARGIN_PX * 2),
+    );
+
+    let draw_centered = |chart: &mut RgbaImage, x: u32, y: u32, size: f32, color, text: &str| {
+        let (w, h) = text_size(size, &*FONT, text);
+        draw_text_mut(
+            chart,
+            color,
+            (x + HEATMAP_CELL_PX.saturating_sub(w) / 2) as i32,
+            (y + HEATMAP_CELL_PX.saturating_sub(h) / 2) as i32,
+            size,
+            &*FONT,
+            text,
+        );
+    };
+
+    for (i, name) in names.iter().enumerate() {
+        let offset = HEATMAP_CELL_PX * i as u32;
+
+        // columns are numbered, names would not fit above them
+        draw_centered(
+            &mut chart,
+            grid_x + offset,
+            grid_y - HEATMAP_CELL_PX,
+            24.0,
+            SECONDARY_COLOR,
+            &(i + 1).to_string(),
+        );
+
+        let label = collage::fit_text(&format!("{}. {}", i + 1, name), 24.0, HEATMAP_LABEL_PX - 10);
+        let (_, h) = text_size(24.0, &*FONT, &label);
+        draw_text_mut(
+            &mut chart,
+            PRIMARY_COLOR,
+            MARGIN_PX as i32,
+            (grid_y + offset + HEATMAP_CELL_PX.saturating_sub(h) / 2) as i32,
+            24.0,
+            &*FONT,
+            &label,
+        );
+
+        for j in 0..names.len() {
+            let x = grid_x + HEATMAP_CELL_PX * j as u32;
+            let y = grid_y + offset;
+            let score = scores.get(i).and_then(|row| row.get(j)).copied().flatten();
+
+            let color = match score {
+                Some(score) if i != j => {
+                    let t = score as f32 / 100.0;
+                    let mix = |c: usize| {
+                        (HEATMAP_LOW_COLOR[c] + (HEATMAP_HIGH_COLOR[c] - HEATMAP_LOW_COLOR[c]) * t)
+                            as u8
+                    };
+                    Rgba([mix(0), mix(1), mix(2), 255])
+                }
+                _ => PLACEHOLDER_COLOR,
+            };
+            draw_filled_rect_mut(
+                &mut chart,
+                Rect::at(x as i32 + 1, y as i32 + 1)
+                    .of_size(HEATMAP_CELL_PX - 2, HEATMAP_CELL_PX - 2),
+                color,
+            );
+
+            let text = match score {
+                _ if i == j => "-".to_string(),
+                Some(score) => score.to_string(),
+                None => "?".to_string(),
+            };
+            draw_centered(&mut chart, x, y, 22.0, PRIMARY_COLOR, &text);
+        }
+    }
+
+    collage::encode_image(chart, OutputFormat::Jpeg, None)
+}
+
+const CLOCK_WIDTH: u32 = 1000;
+const CLOCK_RADIUS: f64 = 300.0;
+const CLOCK_INNER_RADIUS: f64 = 70.0;
+const CLOCK_AREA_PX: u32 = 700;
+const ACTIVITY_LABEL_PX: u32 = 80;
+const ACTIVITY_CELL_PX: u32 = 36;
+// hours[h] is the number of scrobbles in hour h, grid[weekday][h] the same split by weekday
+// starting from monday, both already in the user's time zone
+pub fn create_listening_clock(
+    hours: &[u64; 24],
+    grid: &[[u64; 24]; 7],
+    weekdays: &[String; 7],
+    heading: &str,
+) -> Result<Vec<u8>, anyhow::Error> {
+    let grid_y = HEADER_PX + CLOCK_AREA_PX + 40;
+    let height = grid_y + ACTIVITY_CELL_PX * 7 + MARGIN_PX * 2;
+    let mut chart = RgbaImage::from_pixel(CLOCK_WIDTH, height, BACKGROUND_COLOR);
+
+    draw_text_mut(
+        &mut chart,
+        PRIMARY_COLOR,
+        MARGIN_PX as i32,
+        24,
+        34.0,
+        &*FONT,
+        &collage::fit_text(heading, 34.0, CLOCK_WIDTH - MARGIN_PX * 2),
+    );
+
+    let center_x = CLOCK_WIDTH as f64 / 2.0;
+    let center_y = (HEADER_PX + CLOCK_AREA_PX / 2) as f64;
+    let point = |radius: f64, angle: f64| {
+        Point::new(
+            (center_x + radius * angle.cos()).round() as i32,
+            (center_y + radius * angle.sin()).round() as i32,
+        )
+    };
+
+    // midnight at 12 o'clock, going clockwise
+    let hour_angle = |hour: f64| -std::f64::consts::FRAC_PI_2 + std::f64::consts::TAU * hour / 24.0;
+    let max_hour = hours.iter().copied().max().unwrap_or_default().max(1);
+
+    for (hour, &count) in hours.iter().enumerate() {
+        let (inner, outer) = (
+            point(CLOCK_INNER_RADIUS, hour_angle(hour as f64)),
+            point(CLOCK_RADIUS, hour_angle(hour as f64)),
+        );
+        draw_line_segment_mut(
+            &mut chart,
+            (inner.x as f32, inner.y as f32),
+            (outer.x as f32, outer.y as f32),
+            PLACEHOLDER_COLOR,
+        );
+
+        if count == 0 {
+            continue;
+        }
+
+        let radius = CLOCK_INNER_RADIUS
+            + (CLOCK_RADIUS - CLOCK_INNER_RADIUS) * count as f64 / max_hour as f64;
+        let (start, end) = (
+            hour_angle(hour as f64 + 0.08),
+            hour_angle(hour as f64 + 0.92),
+        );
+        let steps = 12;
+        let mut points = (0..=steps)
+            .map(|step| point(radius, start + (end - start) * step as f64 / steps as f64))
+            .collect::<Vec<_>>();
+        points.extend((0..=steps).rev().map(|step| {
+            point(
+                CLOCK_INNER_RADIUS,
+                start + (end - start) * step as f64 / steps as f64,
+            )
+        }));
+        points.dedup();
+        if points.len() > 2 && points.first() != points.last() {
+            draw_polygon_mut(&mut chart, &points, BAR_COLOR);
+        }
+    }
+
+    for hour in (0..24).step_by(3) {
+        let label = format!("{hour:02}");
+        let (w, h) = text_size(24.0, &*FONT, &label);
+        let at = point(CLOCK_RADIUS + 28.0, hour_angle(hour as f64 + 0.5));
+        draw_text_mut(
+            &mut chart,
+            SECONDARY_COLOR,
+            at.x - w as i32 / 2,
+            at.y - h as i32 / 2,
+            24.0,
+            &*FONT,
+            &label,
+        );
+    }
+
+    let grid_x = MARGIN_PX + ACTIVITY_LABEL_PX;
+    let max_cell = grid
+        .iter()
+        .flatten()
+        .copied()
+        .max()
+        .unwrap_or_default()
+        .max(1);
+    for hour in (0..24).step_by(3) {
+        draw_text_mut(
+            &mut chart,
+            SECONDARY_COLOR,
+            (grid_x + ACTIVITY_CELL_PX * hour) as i32,
+            grid_y as i32 - 30,
+            22.0,
+            &*FONT,
+            &format!("{hour:02}"),
+        );
+    }
+    for (day, row) in grid.iter().enumerate() {
+        let y = grid_y + ACTIVITY_CELL_PX * day as u32;
+        draw_text_mut(
+            &mut chart,
+            PRIMARY_COLOR,
+            MARGIN_PX as i32,
+            y as i32 + 6,
+            22.0,
+            &*FONT,
+            &weekdays[day],
+        );
+        for (hour, &count) in row.iter().enumerate() {
+            let t = count as f32 / max_cell as f32;
+            let mix = |c: usize| {
+                (HEATMAP_LOW_COLOR[c] + (HEATMAP_HIGH_COLOR[c] - HEATMAP_LOW_COLOR[c]) * t) as u8
+            };
+            draw_filled_rect_mut(
+                &mut chart,
+                Rect::at(
+                    (grid_x + ACTIVITY_CELL_PX * hour as u32) as i32 + 1,
+                    y as i32 + 1,
+                )
+                .of_size(ACTIVITY_CELL_PX - 2, ACTIVITY_CELL_PX - 2),
+                Rgba([mix(0), mix(1), mix(2), 255]),
+            );
+        }
+    }
+
+    collage::encode_image(chart, OutputFormat::Jpeg, None)
+}
@@ -1,29 +1,162 @@
+use std::cmp::Ordering;
 use std::sync::LazyLock;
 
 use ab_glyph::FontVec;
 use anyhow::anyhow;
 use bytes::Bytes;
 use image::codecs::jpeg::JpegEncoder;
-use image::{ImageBuffer, Rgba, RgbaImage};
-use imageproc::drawing::draw_text_mut;
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::{DynamicImage, ExtendedColorType, ImageBuffer, ImageEncoder, Rgba, RgbaImage};
+use imageproc::drawing::{draw_text_mut, text_size};
+use strum_macros::FromRepr;
 
 use crate::api_requester::{Album, CLIENT_NOCACHE};
 use crate::config;
 
 const FONT_SIZE: f32 = 24.0;
+const HEADER_FONT_SIZE: f32 = 32.0;
+const RANK_FONT_SIZE: f32 = 30.0;
 const TILE_PX: u32 = 300;
+const GAP_PX: u32 = 10;
+const CAPTION_PX: u32 = 84;
+const HEADER_PX: u32 = 64;
+const CORNER_RADIUS_PX: u32 = 24;
+const DEFAULT_JPEG_QUALITY: u8 = 90;
 pub const MAX_SIZE: u32 = 7;
 pub const MIN_SIZE: u32 = 1;
 
-async fn fetch_album_arts(albums: &[&Album]) -> Vec<Result<Bytes, anyhow::Error>> {
+pub static FONT: LazyLock<FontVec> = LazyLock::new(|| {
+    let font_data = std::fs::read(config::FONT_FILE_PATH).expect("Failed to read font file");
+    FontVec::try_from_vec(font_data).expect("Error constructing Font")
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, FromRepr)]
+#[repr(u8)]
+pub enum Captions {
+    #[default]
+    Overlay,
+    Below,
+    Hidden,
+}
+
+impl Captions {
+    pub fn next(self) -> Captions {
+        match self {
+            Captions::Overlay => Captions::Below,
+            Captions::Below => Captions::Hidden,
+            Captions::Hidden => Captions::Overlay,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, FromRepr)]
+#[repr(u8)]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl Theme {
+    fn background(&self) -> Rgba<u8> {
+        match self {
+            Theme::Dark => Rgba([0, 0, 0, 255]),
+            Theme::Light => Rgba([245, 245, 245, 255]),
+        }
+    }
+
+    fn foreground(&self) -> Rgba<u8> {
+        match self {
+            Theme::Dark => Rgba([255, 255, 255, 255]),
+            Theme::Light => Rgba([20, 20, 20, 255]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, FromRepr)]
+#[repr(u8)]
+pub enum OutputFormat {
+    Jpeg,
+    Png,
+    Webp,
+}
+
+impl OutputFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Jpeg => "jpg",
+            OutputFormat::Png => "png",
+            OutputFormat::Webp => "webp",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CollageStyle {
+    pub captions: Captions,
+    pub ranks: bool,
+    pub gaps: bool,
+    pub rounded: bool,
+    pub theme: Theme,
+    pub header: bool,
+    pub rainbow: bool,
+    // None unless a format word was given, see output_format
+    pub format: Option<OutputFormat>,
+    // jpeg only, 1-100
+    pub quality: Option<u8>,
+    pub as_file: bool,
+}
+
+impl CollageStyle {
+    // clean collages are png, unless a format or a jpeg quality was asked for
+    pub fn output_format(&self) -> OutputFormat {
+        self.format.unwrap_or(
+            if self.captions == Captions::Hidden && self.quality.is_none() {
+                OutputFormat::Png
+            } else {
+                OutputFormat::Jpeg
+            },
+        )
+    }
+
+    // returns false if the word is not a style option
+    pub fn parse_word(&mut self, word: &str) -> bool {
+        // q85
+        if let Some(quality) = word
+            .strip_prefix('q')
+            .and_then(|q| q.parse::<u8>().ok())
+            .filter(|q| (1..=100).contains(q))
+        {
+            self.quality = Some(quality);
+            return true;
+        }
+
+        match word {
+            "clean" | "notext" | "nonames" => self.captions = Captions::Hidden,
+            "below" | "captions" => self.captions = Captions::Below,
+            "ranks" | "ranked" | "numbers" => self.ranks = true,
+            "gaps" | "gapped" => self.gaps = true,
+            "round" | "rounded" => self.rounded = true,
+            "light" => self.theme = Theme::Light,
+            "dark" => self.theme = Theme::Dark,
+            "header" | "title" => self.header = true,
+            "rainbow" | "colour" | "color" | "colours" | "colors" => self.rainbow = true,
+            "jpg" | "jpeg" => self.format = Some(OutputFormat::Jpeg),
+            "png" => self.format = Some(OutputFormat::Png),
+            "webp" => self.format = Some(OutputFormat::Webp),
+            "file" | "document" | "doc" => self.as_file = true,
+            _ => return false,
+        }
+
+        true
+    }
+}
+
+pub async fn fetch_images(urls: &[&str]) -> Vec<Result<Bytes, anyhow::Error>> {
     let mut handles = Vec::new();
-    albums
-        .iter()
-        .map(|album| {
-            CLIENT_NOCACHE
-                .get(album.album_art_url.as_ref().unwrap())
-                .send()
-        })
+    urls.iter()
+        .map(|url| CLIENT_NOCACHE.get(*url).send())
         .for_each(|fut| {
             let handle = tokio::spawn(async move {
                 let resp = fut.await;
@@ -44,19 +177,178 @@ async fn fetch_album_arts(albums: &[&Album]) -> Vec<Result<Bytes, anyhow::Error>
     bytes_results
 }
 
+fn draw_text_with_outline(image: &mut RgbaImage, x: i32, y: i32, font_size: f32, text: &str) {
+    let text_color = Rgba([255u8, 255, 255, 255]);
+    let outline_color = Rgba([0u8, 0, 0, 255]);
+
+    for (dx, dy) in [
+        (-2, -2),
+        (-2, 0),
+        (-2, 2),
+        (0, -2),
+        (0, 2),
+        (2, -2),
+        (2, 0),
+        (2, 2),
+    ] {
+        draw_text_mut(
+            image,
+            outline_color,
+            x + dx,
+            y + dy,
+            font_size,
+            &*FONT,
+            text,
+        );
+    }
+
+    draw_text_mut(image, text_color, x, y, font_size, &*FONT, text);
+}
+
+// shortens the text with an ellipsis until it fits in max_width
+pub fn fit_text(text: &str, font_size: f32, max_width: u32) -> String {
+    if text_size(font_size, &*FONT, text).0 <= max_width {
+        return text.to_string();
+    }
+
+    let mut chars = text.chars().collect::<Vec<_>>();
+    while chars.pop().is_some() {
+        let candidate = format!("{}…", chars.iter().collect::<String>());
+        if text_size(font_size, &*FONT, &candidate).0 <= max_width {
+            return candidate;
+        }
+    }
+
+    String::new()
+}
+
+pub fn round_corners(tile: &mut RgbaImage, radius: u32) {
+    let (w, h) = tile.dimensions();
+    let r = radius.min(w / 2).min(h / 2) as i64;
+    let (w, h) = (w as i64, h as i64);
+
+    for (x, y, pixel) in tile.enumerate_pixels_mut() {
+        let (x, y) = (x as i64, y as i64);
+        let cx = if x < r {
+            r
+        } else if x >= w - r {
+            w - r - 1
+        } else {
+            continue;
+        };
+        let cy = if y < r {
+            r
+        } else if y >= h - r {
+            h - r - 1
+        } else {
+            continue;
+        };
+
+        if (x - cx).pow(2) + (y - cy).pow(2) > r * r {
+            pixel[3] = 0;
+        }
+    }
+}
+
+fn rgb_to_hsv(r: u8, g: u8, b: u8) -> (f32, f32, f32) {
+    let (r, g, b) = (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    let saturation = if max == 0.0 { 0.0 } else { delta / max };
+
+    (hue, saturation, max)
+}
+
+// hue of the most common saturated colour, or None for greyish covers, and the average brightness
+fn dominant_colour(tile: &RgbaImage) -> (Option<f32>, f32) {
+    const HUE_BINS: usize = 36;
+
+    let small = image::imageops::thumbnail(tile, 32, 32);
+    let mut bins = [(0.0f32, 0.0f32); HUE_BINS]; // (weight, weighted hue sum)
+    let mut total_value = 0.0;
+
+    for pixel in small.pixels() {
+        let (hue, saturation, value) = rgb_to_hsv(pixel[0], pixel[1], pixel[2]);
+        total_value += value;
+
+        if saturation > 0.2 && value > 0.2 {
+            let weight = saturation * value;
+            let bin = &mut bins[(hue / 360.0 * HUE_BINS as f32) as usize % HUE_BINS];
+            bin.0 += weight;
+            bin.1 += weight * hue;
+        }
+    }
+
+    let pixel_count = (small.width() * small.height()).max(1) as f32;
+    let average_value = total_value / pixel_count;
+    let coloured_weight: f32 = bins.iter().map(|b| b.0).sum();
+
+    // mostly grey, black or white
+    if coloured_weight < pixel_count * 0.05 {
+        return (None, average_value);
+    }
+
+    let (weight, hue_sum) = bins
+        .iter()
+        .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal))
+        .copied()
+        .unwrap_or_default();
+
+    (Some(hue_sum / weight.max(f32::EPSILON)), average_value)
+}
+
+// coloured covers first along the hue wheel, then grey ones from light to dark
+fn colour_sort_key(tile: Option<&RgbaImage>) -> (u8, f32) {
+    match tile.map(dominant_colour) {
+        Some((Some(hue), _)) => (0, hue),
+        Some((None, value)) => (1, -value),
+        None => (2, 0.0),
+    }
+}
+
 pub async fn create_collage(
     albums: &[Album],
     size: u32,
-    text: bool,
+    style: &CollageStyle,
+    header_text: &str,
 ) -> Result<Vec<u8>, anyhow::Error> {
-    static FONT: LazyLock<FontVec> = LazyLock::new(|| {
-        let font_data = std::fs::read(config::FONT_FILE_PATH).expect("Failed to read font file");
-        FontVec::try_from_vec(font_data).expect("Error constructing Font")
-    });
+    let gap = if style.gaps { GAP_PX } else { 0 };
+    let caption_px = if style.captions == Captions::Below {
+        CAPTION_PX
+    } else {
+        0
+    };
+    let header_px = if style.header { HEADER_PX } else { 0 };
+
+    let collage_width: u32 = TILE_PX * size + gap * (size + 1);
+    let collage_height: u32 = header_px + (TILE_PX + caption_px) * size + gap * (size + 1);
 
-    let collage_size: u32 = TILE_PX * size;
+    let mut collage =
+        ImageBuffer::from_pixel(collage_width, collage_height, style.theme.background());
 
-    let mut collage = ImageBuffer::from_pixel(collage_size, collage_size, Rgba([0, 0, 0, 255]));
+    if style.header {
+        let margin = gap.max(GAP_PX);
+        draw_text_mut(
+            &mut collage,
+            style.theme.foreground(),
+            margin as i32,
+            ((HEADER_PX as f32 - HEADER_FONT_SIZE) / 2.0) as i32,
+            HEADER_FONT_SIZE,
+            &*FONT,
+            &fit_text(header_text, HEADER_FONT_SIZE, collage_width - margin * 2),
+        );
+    }
 
     let albums = albums
         .iter()
@@ -64,80 +356,144 @@ pub async fn create_collage(
         .take((size * size).try_into().unwrap())
         .collect::<Vec<_>>();
 
-    let tiles_bytes_vec = fetch_album_arts(&albums).await;
-
-    for (i, album) in albums.iter().enumerate() {
-        let tiles_bytes = &tiles_bytes_vec[i];
-
-        let row = i as u32 / size;
-        let col = i as u32 % size;
-        let tile_x = col * TILE_PX;
-        let tile_y = row * TILE_PX;
+    let urls = albums
+        .iter()
+        .map(|x| x.album_art_url.as_deref().unwrap())
+        .collect::<Vec<_>>();
+    let tiles_bytes_vec = fetch_images(&urls).await;
 
-        match tiles_bytes {
-            Ok(bytes) => {
-                let mut tile = image::load_from_memory(bytes).ok().unwrap_or_default();
+    // (rank, album, decoded tile), so that ranks survive reordering
+    let mut tiles = albums
+        .iter()
+        .zip(tiles_bytes_vec)
+        .enumerate()
+        .map(|(i, (album, tile_bytes))| {
+            let tile = tile_bytes.ok().map(|bytes| {
+                let mut tile = image::load_from_memory(&bytes).ok().unwrap_or_default();
                 if tile.width() > TILE_PX {
                     tile = tile.thumbnail(TILE_PX, TILE_PX);
                 }
-                image::imageops::overlay(&mut collage, &tile, tile_x.into(), tile_y.into());
-            }
-            Err(_) => {
-                // continue;
+                tile.to_rgba8()
+            });
+            (i, album, tile)
+        })
+        .collect::<Vec<_>>();
+
+    if style.rainbow {
+        let mut keyed = tiles
+            .into_iter()
+            .map(|entry| (colour_sort_key(entry.2.as_ref()), entry))
+            .collect::<Vec<_>>();
+        keyed.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+        tiles = keyed.into_iter().map(|(_, entry)| entry).collect();
+    }
+
+    for (position, (i, album, tile)) in tiles.into_iter().enumerate() {
+        let row = position as u32 / size;
+        let col = position as u32 % size;
+        let tile_x = gap + col * (TILE_PX + gap);
+        let tile_y = header_px + gap + row * (TILE_PX + caption_px + gap);
+
+        if let Some(mut tile) = tile {
+            if style.rounded {
+                round_corners(&mut tile, CORNER_RADIUS_PX);
             }
-        };
+            image::imageops::overlay(&mut collage, &tile, tile_x.into(), tile_y.into());
+        }
 
         // Draw text
 
-        if text {
-            let text_color = Rgba([255u8, 255, 255, 255]);
-            let outline_color = Rgba([0u8, 0, 0, 255]);
+        if style.captions == Captions::Overlay || style.ranks {
             let mut text_image = RgbaImage::from_pixel(TILE_PX, TILE_PX, Rgba([0, 0, 0, 0]));
+            let tile_size = TILE_PX as i32;
 
-            let mut draw_text = |x: i32, y: i32, text: &str, fg: bool| {
-                draw_text_mut(
+            if style.ranks {
+                draw_text_with_outline(
                     &mut text_image,
-                    if fg { text_color } else { outline_color },
-                    x,
-                    y,
+                    12,
+                    8,
+                    RANK_FONT_SIZE,
+                    &(i + 1).to_string(),
+                );
+            }
+
+            if style.captions == Captions::Overlay {
+                draw_text_with_outline(&mut text_image, 10, tile_size - 70, FONT_SIZE, &album.name);
+                draw_text_with_outline(
+                    &mut text_image,
+                    10,
+                    tile_size - 50,
                     FONT_SIZE,
-                    &*FONT,
-                    text,
-                )
-            };
-
-            let mut draw_text_with_outline = |x: i32, y: i32, text: &str| {
-                draw_text(x - 2, y - 2, text, false);
-                draw_text(x - 2, y, text, false);
-                draw_text(x - 2, y + 2, text, false);
-                draw_text(x, y - 2, text, false);
-                draw_text(x, y + 2, text, false);
-                draw_text(x + 2, y - 2, text, false);
-                draw_text(x + 2, y, text, false);
-                draw_text(x + 2, y + 2, text, false);
-
-                draw_text(x, y, text, true);
-            };
+                    &album.artist,
+                );
+                draw_text_with_outline(
+                    &mut text_image,
+                    10,
+                    tile_size - 30,
+                    FONT_SIZE,
+                    &format!("{} plays", album.user_playcount),
+                );
+            }
 
-            let tile_size = TILE_PX as i32;
+            image::imageops::overlay(&mut collage, &text_image, tile_x.into(), tile_y.into());
+        }
 
-            draw_text_with_outline(10, tile_size - 70, &album.name);
-            draw_text_with_outline(10, tile_size - 50, &album.artist);
-            draw_text_with_outline(
-                10,
-                tile_size - 30,
-                &format!("{} plays", album.user_playcount),
-            );
+        if style.captions == Captions::Below {
+            let lines = [
+                album.name.clone(),
+                album.artist.clone(),
+                format!("{} plays", album.user_playcount),
+            ];
 
-            image::imageops::overlay(&mut collage, &text_image, tile_x.into(), tile_y.into());
+            for (line_i, line) in lines.iter().enumerate() {
+                draw_text_mut(
+                    &mut collage,
+                    style.theme.foreground(),
+                    tile_x as i32,
+                    (tile_y + TILE_PX + 4) as i32 + line_i as i32 * 26,
+                    FONT_SIZE,
+                    &*FONT,
+                    &fit_text(line, FONT_SIZE, TILE_PX),
+                );
+            }
         }
     }
 
-    let mut jpeg_bytes: Vec<u8> = Vec::new();
-    let mut encoder = JpegEncoder::new(&mut jpeg_bytes);
-    encoder
-        .encode_image(&collage)
-        .expect("Failed to encode JPEG");
+    encode_image(collage, style.output_format(), style.quality)
+}
+
+pub fn encode_image(
+    image: RgbaImage,
+    format: OutputFormat,
+    quality: Option<u8>,
+) -> Result<Vec<u8>, anyhow::Error> {
+    let (width, height) = image.dimensions();
+    let mut bytes: Vec<u8> = Vec::new();
+
+    match format {
+        OutputFormat::Jpeg => {
+            // jpeg has no alpha channel
+            let rgb_image = DynamicImage::ImageRgba8(image).into_rgb8();
+            JpegEncoder::new_with_quality(&mut bytes, quality.unwrap_or(DEFAULT_JPEG_QUALITY))
+                .encode_image(&rgb_image)?;
+        }
+        OutputFormat::Png => {
+            PngEncoder::new(&mut bytes).write_image(
+                image.as_raw(),
+                width,
+                height,
+                ExtendedColorType::Rgba8,
+            )?;
+        }
+        OutputFormat::Webp => {
+            WebPEncoder::new_lossless(&mut bytes).write_image(
+                image.as_raw(),
+                width,
+                height,
+                ExtendedColorType::Rgba8,
+            )?;
+        }
+    }
 
-    Ok(jpeg_bytes)
+    Ok(bytes)
 }
@@ -0,0 +1,212 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    sync::{Arc, LazyLock},
+    time::Duration,
+};
+
+use crate::api_requester::{self, ApiType, TimePeriod};
+
+// listenbrainz stats don't go past 100 entries
+const ENTRY_LIMIT: usize = 100;
+const ARTIST_WEIGHT: f64 = 0.5;
+const ALBUM_WEIGHT: f64 = 0.3;
+const TRACK_WEIGHT: f64 = 0.2;
+
+// normalized key -> (display name, weight)
+type Weights = HashMap<String, (String, f64)>;
+
+pub struct Compatibility {
+    // 0 to 100
+    pub score: u32,
+    pub artists: Option<f64>,
+    pub albums: Option<f64>,
+    pub tracks: Option<f64>,
+    // shared artists with their share of the artist similarity, biggest first
+    pub shared_artists: Vec<(String, f64)>,
+}
+
+// case, punctuation and "remastered"-style suffixes shouldn't stop two entries from matching
+fn normalize_name(name: &str) -> String {
+    let name = name.to_lowercase();
+    let name = [" (", " [", " - "]
+        .iter()
+        .filter_map(|sep| name.find(sep))
+        .min()
+        .filter(|&i| i > 0)
+        .map(|i| &name[..i])
+        .unwrap_or(&name);
+
+    name.chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// log scaling keeps a single heavily played artist from deciding the whole score
+fn to_weights(entries: impl Iterator<Item = (String, String, u64)>) -> Weights {
+    let mut weights: Weights = HashMap::new();
+    for (key, display_name, plays) in entries {
+        let weight = &mut weights.entry(key).or_insert((display_name, 0.0)).1;
+        *weight += (1.0 + plays as f64).ln();
+    }
+    weights
+}
+
+fn cosine(a: &Weights, b: &Weights) -> Option<(f64, Vec<(String, f64)>)> {
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+
+    let norm = |x: &Weights| x.values().map(|(_, w)| w * w).sum::<f64>().sqrt();
+    let (norm_a, norm_b) = (norm(a), norm(b));
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return None;
+    }
+
+    let mut shared = a
+        .iter()
+        .filter_map(|(key, (name, wa))| b.get(key).map(|(_, wb)| (name.clone(), wa * wb)))
+        .collect::<Vec<_>>();
+    let dot: f64 = shared.iter().map(|x| x.1).sum();
+
+    shared.sort_by(|x, y| y.1.total_cmp(&x.1));
+    if dot > 0.0 {
+        for entry in &mut shared {
+            entry.1 /= dot;
+        }
+    }
+
+    Some((dot / (norm_a * norm_b), shared))
+}
+
+pub struct Profile {
+    artists: Option<Weights>,
+    albums: Option<Weights>,
+    tracks: Option<Weights>,
+}
+
+// top lists change slowly, group matrices would otherwise refetch every member for every pair
+static PROFILES: LazyLock<moka::future::Cache<String, Arc<Profile>>> = LazyLock::new(|| {
+    moka::future::Cache::builder()
+        .max_capacity(2_000)
+        .time_to_live(Duration::from_secs(60 * 60 * 6))
+        .build()
+});
+
+async fn fetch_profile_uncached(
+    username: &str,
+    api_type: &ApiType,
+    period: &TimePeriod,
+) -> Profile {
+    let (artists, albums, tracks) = tokio::join!(
+        api_requester::fetch_artists(username, period, api_type, Some(ENTRY_LIMIT)),
+        api_requester::fetch_albums(username, period, api_type, Some(ENTRY_LIMIT)),
+        api_requester::fetch_tracks(username, period, api_type, Some(ENTRY_LIMIT)),
+    );
+
+    let artists = artists.ok().map(|x| {
+        to_weights(
+            x.into_iter()
+                .map(|e| (normalize_name(&e.name), e.name, e.user_playcount)),
+        )
+    });
+    let albums = albums.ok().map(|x| {
+        to_weights(x.into_iter().map(|e| {
+            (
+                format!("{}\0{}", normalize_name(&e.artist), normalize_name(&e.name)),
+                e.name,
+                e.user_playcount,
+            )
+        }))
+    });
+    let tracks = tracks.ok().map(|x| {
+        to_weights(x.into_iter().map(|e| {
+            (
+                format!("{}\0{}", normalize_name(&e.artist), normalize_name(&e.name)),
+                e.name,
+                e.user_playcount,
+            )
+        }))
+    });
+
+    Profile {
+        artists,
+        albums,
+        tracks,
+    }
+}
+
+pub async fn fetch_profile(
+    username: &str,
+    api_type: &ApiType,
+    period: &TimePeriod,
+) -> Arc<Profile> {
+    let key = format!("{api_type} {period} {}", username.to_lowercase());
+    if let Some(profile) = PROFILES.get(&key).await {
+        return profile;
+    }
+
+    let profile = Arc::new(fetch_profile_uncached(username, api_type, period).await);
+    // a partial profile would score on fewer categories until it expires, so only keep complete ones
+    if profile.artists.is_some() && profile.albums.is_some() && profile.tracks.is_some() {
+        PROFILES.insert(key, profile.clone()).await;
+    }
+    profile
+}
+
+pub fn compare(profile1: &Profile, profile2: &Profile) -> Option<Compatibility> {
+    let similarity = |a: &Option<Weights>, b: &Option<Weights>| cosine(a.as_ref()?, b.as_ref()?);
+
+    let artists = similarity(&profile1.artists, &profile2.artists);
+    let albums = similarity(&profile1.albums, &profile2.albums).map(|x| x.0);
+    let tracks = similarity(&profile1.tracks, &profile2.tracks).map(|x| x.0);
+
+    if artists.is_none() && albums.is_none() && tracks.is_none() {
+        return None;
+    }
+
+    // entity types we couldn't fetch don't count against the score
+    let weighted = [
+        (artists.as_ref().map(|x| x.0), ARTIST_WEIGHT),
+        (albums, ALBUM_WEIGHT),
+        (tracks, TRACK_WEIGHT),
+    ];
+    let total_weight: f64 = weighted.iter().filter(|x| x.0.is_some()).map(|x| x.1).sum();
+    let combined: f64 = weighted
+        .iter()
+        .filter_map(|(sim, weight)| sim.map(|s| s * weight))
+        .sum::<f64>()
+        / total_weight;
+
+    // cosine similarity between two people is rarely high, the square root spreads it out
+    let score = (combined.sqrt() * 100.0).round().clamp(0.0, 100.0) as u32;
+
+    let (artists, shared_artists) = match artists {
+        Some((sim, shared)) => (Some(sim), shared),
+        None => (None, vec![]),
+    };
+
+    Some(Compatibility {
+        score,
+        artists,
+        albums,
+        tracks,
+        shared_artists,
+    })
+}
+
+pub async fn compute(
+    user1: (&str, &ApiType),
+    user2: (&str, &ApiType),
+    period: &TimePeriod,
+) -> Result<Compatibility, Box<dyn Error + Send + Sync>> {
+    let (profile1, profile2) = tokio::join!(
+        fetch_profile(user1.0, user1.1, period),
+        fetch_profile(user2.0, user2.1, period),
+    );
+
+    compare(&profile1, &profile2).ok_or_else(|| Box::from("No top lists to compare"))
+}
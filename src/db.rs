@@ -1,6 +1,9 @@
+use chrono_tz::Tz;
+use num_format::Locale;
 use rusqlite::{Connection, Result, params};
 
-use crate::api_requester::ApiType;
+use crate::api_requester::{ApiType, TimePeriod};
+use crate::i18n::Personality;
 
 #[derive(Clone, Debug)]
 pub struct User {
@@ -9,6 +12,13 @@ pub struct User {
     api_type: String,
     pub profile_shown: bool,
     pub cover_shown: bool,
+    pub notify_milestones: bool,
+    pub broadcasts: bool,
+    pub analytics: bool,
+    timezone: Option<String>,
+    number_locale: Option<String>,
+    language: Option<String>,
+    personality: Option<String>,
 }
 
 impl User {
@@ -25,12 +35,157 @@ impl User {
             api_type: api_type.to_string(),
             profile_shown,
             cover_shown,
+            notify_milestones: false,
+            broadcasts: true,
+            analytics: true,
+            timezone: None,
+            number_locale: None,
+            language: None,
+            personality: None,
         }
     }
 
     pub fn api_type(&self) -> ApiType {
         self.api_type.parse().unwrap_or(ApiType::Lastfm)
     }
+
+    pub fn timezone(&self) -> Tz {
+        self.timezone
+            .as_deref()
+            .and_then(|x| x.parse().ok())
+            .unwrap_or(Tz::UTC)
+    }
+
+    pub fn set_timezone(&mut self, timezone: Tz) {
+        self.timezone = Some(timezone.name().to_string());
+    }
+
+    // how counts are written, 1,234 or 1.234 or 1 234
+    pub fn locale(&self) -> Locale {
+        self.number_locale
+            .as_deref()
+            .and_then(|x| Locale::from_name(x).ok())
+            .unwrap_or(Locale::en)
+    }
+
+    pub fn set_locale(&mut self, locale: Locale) {
+        self.number_locale = Some(locale.name().to_string());
+    }
+
+    // None follows the telegram client's language
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref()
+    }
+
+    pub fn set_language(&mut self, language: Option<&str>) {
+        self.language = language.map(str::to_owned);
+    }
+
+    // None uses the default personality
+    pub fn personality(&self) -> Option<Personality> {
+        self.personality.as_deref().and_then(|x| x.parse().ok())
+    }
+
+    pub fn set_personality(&mut self, personality: Option<Personality>) {
+        self.personality = personality.map(|x| x.to_string());
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ChatSettings {
+    pub chat_id: i64,
+    // space separated command names
+    disabled_commands: String,
+    pub clean_collages: bool,
+    // space separated forum topic ids, empty allows all of them
+    allowed_topics: String,
+    pub slashless: bool,
+    default_period: Option<String>,
+}
+
+impl ChatSettings {
+    pub fn new(chat_id: i64) -> ChatSettings {
+        ChatSettings {
+            chat_id,
+            disabled_commands: String::new(),
+            clean_collages: false,
+            allowed_topics: String::new(),
+            slashless: true,
+            default_period: None,
+        }
+    }
+
+    pub fn is_disabled(&self, command: &str) -> bool {
+        self.disabled_commands
+            .split_whitespace()
+            .any(|x| x == command)
+    }
+
+    pub fn disabled_commands(&self) -> Vec<&str> {
+        self.disabled_commands.split_whitespace().collect()
+    }
+
+    pub fn toggle_command(&mut self, command: &str) {
+        self.disabled_commands = toggle_word(&self.disabled_commands, command);
+    }
+
+    pub fn allows_topic(&self, topic_id: i32) -> bool {
+        self.allowed_topics.is_empty() || self.is_allowed_topic(topic_id)
+    }
+
+    pub fn is_allowed_topic(&self, topic_id: i32) -> bool {
+        let topic_id = topic_id.to_string();
+        self.allowed_topics
+            .split_whitespace()
+            .any(|x| x == topic_id)
+    }
+
+    pub fn allowed_topics_count(&self) -> usize {
+        self.allowed_topics.split_whitespace().count()
+    }
+
+    pub fn toggle_topic(&mut self, topic_id: i32) {
+        self.allowed_topics = toggle_word(&self.allowed_topics, &topic_id.to_string());
+    }
+
+    // None shows the period buttons as usual
+    pub fn default_period(&self) -> Option<TimePeriod> {
+        self.default_period.as_deref().and_then(|x| x.parse().ok())
+    }
+
+    pub fn set_default_period(&mut self, period: Option<TimePeriod>) {
+        self.default_period = period.map(|x| x.to_string());
+    }
+}
+
+fn toggle_word(words: &str, word: &str) -> String {
+    let mut words = words.split_whitespace().collect::<Vec<_>>();
+    match words.iter().position(|x| *x == word) {
+        Some(i) => {
+            words.remove(i);
+        }
+        None => words.push(word),
+    }
+    words.join(" ")
+}
+
+const USER_COLUMNS: &str = "users.tg_user_id, users.account_username, users.api_type, users.profile_shown, users.cover_shown, users.notify_milestones, users.timezone, users.number_locale, users.language, users.personality, users.broadcasts, users.analytics";
+
+fn user_from_row(row: &rusqlite::Row) -> Result<User> {
+    Ok(User {
+        tg_user_id: row.get::<_, i64>(0)? as u64,
+        account_username: row.get(1)?,
+        api_type: row.get(2)?,
+        profile_shown: row.get(3)?,
+        cover_shown: row.get(4)?,
+        notify_milestones: row.get(5)?,
+        timezone: row.get(6)?,
+        number_locale: row.get(7)?,
+        language: row.get(8)?,
+        personality: row.get(9)?,
+        broadcasts: row.get(10)?,
+        analytics: row.get(11)?,
+    })
 }
 
 pub struct Db {
@@ -39,7 +194,11 @@ pub struct Db {
 
 impl Db {
     pub fn new() -> Db {
-        let conn = Connection::open("users.sqlite").unwrap();
+        Db::open("users.sqlite")
+    }
+
+    pub fn open(path: &str) -> Db {
+        let conn = Connection::open(path).unwrap();
         let _ = conn.execute(
             "CREATE TABLE IF NOT EXISTS users (
             tg_user_id              INTEGER PRIMARY KEY,
@@ -50,39 +209,557 @@ impl Db {
             )",
             (),
         );
+        // telegram usernames are only known once a user talks to the bot, for @mentions
+        let _ = conn.execute("ALTER TABLE users ADD COLUMN tg_username TEXT", ());
+        let _ = conn.execute(
+            "CREATE INDEX IF NOT EXISTS users_tg_username ON users (tg_username)",
+            (),
+        );
+        let _ = conn.execute(
+            "ALTER TABLE users ADD COLUMN notify_milestones INTEGER NOT NULL DEFAULT 0",
+            (),
+        );
+        // IANA name, NULL means UTC
+        let _ = conn.execute("ALTER TABLE users ADD COLUMN timezone TEXT", ());
+        // num_format locale name, NULL means en
+        let _ = conn.execute("ALTER TABLE users ADD COLUMN number_locale TEXT", ());
+        let _ = conn.execute("ALTER TABLE users ADD COLUMN language TEXT", ());
+        let _ = conn.execute("ALTER TABLE users ADD COLUMN personality TEXT", ());
+        // announcements from the owner, opted out in /preferences
+        let _ = conn.execute(
+            "ALTER TABLE users ADD COLUMN broadcasts INTEGER NOT NULL DEFAULT 1",
+            (),
+        );
+        // usage events, opted out in /preferences
+        let _ = conn.execute(
+            "ALTER TABLE users ADD COLUMN analytics INTEGER NOT NULL DEFAULT 1",
+            (),
+        );
+        // unix time of the last command, for counting active users
+        let _ = conn.execute("ALTER TABLE users ADD COLUMN last_seen INTEGER", ());
+        // group settings, only picked by admins
+        let _ = conn.execute(
+            "CREATE TABLE IF NOT EXISTS chats (
+            chat_id                 INTEGER PRIMARY KEY,
+            personality             TEXT
+            )",
+            (),
+        );
+        let _ = conn.execute(
+            "ALTER TABLE chats ADD COLUMN disabled_commands TEXT NOT NULL DEFAULT ''",
+            (),
+        );
+        let _ = conn.execute(
+            "ALTER TABLE chats ADD COLUMN clean_collages INTEGER NOT NULL DEFAULT 0",
+            (),
+        );
+        let _ = conn.execute(
+            "ALTER TABLE chats ADD COLUMN allowed_topics TEXT NOT NULL DEFAULT ''",
+            (),
+        );
+        // the "commands without a /" parsing
+        let _ = conn.execute(
+            "ALTER TABLE chats ADD COLUMN slashless INTEGER NOT NULL DEFAULT 1",
+            (),
+        );
+        // TimePeriod name, NULL shows the period buttons
+        let _ = conn.execute("ALTER TABLE chats ADD COLUMN default_period TEXT", ());
+        // highest milestones already announced, so restarts don't repeat them
+        let _ = conn.execute(
+            "CREATE TABLE IF NOT EXISTS milestones (
+            tg_user_id              INTEGER PRIMARY KEY,
+            account_username        TEXT NOT NULL,
+            scrobbles               INTEGER NOT NULL,
+            artists                 INTEGER NOT NULL
+            )",
+            (),
+        );
+        let _ = conn.execute(
+            "CREATE TABLE IF NOT EXISTS milestone_chats (
+            chat_id                 INTEGER PRIMARY KEY
+            )",
+            (),
+        );
+        // the bot api can't list group members, so remember who used the bot where
+        let _ = conn.execute(
+            "CREATE TABLE IF NOT EXISTS chat_members (
+            chat_id                 INTEGER NOT NULL,
+            tg_user_id              INTEGER NOT NULL,
+            last_seen               INTEGER NOT NULL,
+            PRIMARY KEY (chat_id, tg_user_id)
+            )",
+            (),
+        );
+        // analytics, time in unix milliseconds
+        let _ = conn.execute(
+            "CREATE TABLE IF NOT EXISTS events (
+            time                    INTEGER NOT NULL,
+            event_type              TEXT NOT NULL,
+            user_id                 TEXT,
+            language                TEXT
+            )",
+            (),
+        );
+        let _ = conn.execute(
+            "CREATE INDEX IF NOT EXISTS events_time ON events (time)",
+            (),
+        );
+        // created_at is unix seconds, old rows get pruned
+        let _ = conn.execute(
+            "CREATE TABLE IF NOT EXISTS callback_payloads (
+            key                     TEXT PRIMARY KEY,
+            payload                 BLOB NOT NULL,
+            created_at              INTEGER NOT NULL
+            )",
+            (),
+        );
+        let _ = conn.execute(
+            "CREATE INDEX IF NOT EXISTS callback_payloads_created_at ON callback_payloads (created_at)",
+            (),
+        );
+
+        // users who opted out of analytics without registering, or before unlinking
+        let _ = conn.execute(
+            "CREATE TABLE IF NOT EXISTS analytics_opt_outs (
+            tg_user_id              INTEGER PRIMARY KEY
+            )",
+            (),
+        );
+        let _ = conn.execute(
+            "CREATE TABLE IF NOT EXISTS meta (
+            key                     TEXT PRIMARY KEY,
+            value                   TEXT NOT NULL
+            )",
+            (),
+        );
 
         Db { conn }
     }
 
+    // for the health check
+    pub fn ping(&self) -> Result<()> {
+        self.conn.query_row("SELECT 1", [], |_| Ok(()))
+    }
+
     pub fn fetch_user(&self, tg_user_id: u64) -> Option<User> {
+        self.conn
+            .query_row(
+                &format!("SELECT {USER_COLUMNS} FROM users WHERE tg_user_id = ?1 LIMIT 1"),
+                [tg_user_id as i64],
+                user_from_row,
+            )
+            .ok()
+    }
+
+    pub fn fetch_user_by_tg_username(&self, tg_username: &str) -> Option<User> {
+        self.conn
+            .query_row(
+                &format!("SELECT {USER_COLUMNS} FROM users WHERE tg_username = ?1 LIMIT 1"),
+                [tg_username.to_lowercase()],
+                user_from_row,
+            )
+            .ok()
+    }
+
+    pub fn fetch_milestone_users(&self) -> Vec<User> {
         let mut stmt = self
             .conn
-            .prepare("SELECT * FROM users WHERE tg_user_id = ?1 LIMIT 1")
+            .prepare(&format!(
+                "SELECT {USER_COLUMNS} FROM users WHERE notify_milestones = 1"
+            ))
             .unwrap();
 
-        stmt.query_map([tg_user_id as i64], |row| {
-            Ok(User {
-                tg_user_id: row.get::<_, i64>(0)? as u64,
-                account_username: row.get(1)?,
-                api_type: row.get(2)?,
-                profile_shown: row.get(3)?,
-                cover_shown: row.get(4)?,
-            })
-        })
-        .unwrap()
-        .next()
-        .map(|x| x.unwrap())
+        stmt.query_map([], user_from_row)
+            .unwrap()
+            .filter_map(|x| x.ok())
+            .collect()
+    }
+
+    pub fn fetch_broadcast_users(&self) -> Vec<User> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {USER_COLUMNS} FROM users WHERE broadcasts = 1"
+            ))
+            .unwrap();
+
+        stmt.query_map([], user_from_row)
+            .unwrap()
+            .filter_map(|x| x.ok())
+            .collect()
+    }
+
+    // (api type, registered users)
+    pub fn count_users_by_api_type(&self) -> Vec<(String, u64)> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT api_type, COUNT(*) FROM users GROUP BY api_type ORDER BY COUNT(*) DESC",
+            )
+            .unwrap();
+
+        stmt.query_map([], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as u64)))
+            .unwrap()
+            .filter_map(|x| x.ok())
+            .collect()
+    }
+
+    pub fn count_active_users(&self, since: u64) -> u64 {
+        self.conn
+            .query_row(
+                "SELECT COUNT(*) FROM users WHERE last_seen >= ?1",
+                [since as i64],
+                |row| row.get::<_, i64>(0),
+            )
+            .unwrap_or_default() as u64
+    }
+
+    pub fn fetch_last_seen(&self, tg_user_id: u64) -> Option<u64> {
+        self.conn
+            .query_row(
+                "SELECT last_seen FROM users WHERE tg_user_id = ?1",
+                [tg_user_id as i64],
+                |row| row.get::<_, Option<i64>>(0),
+            )
+            .ok()
+            .flatten()
+            .map(|x| x as u64)
+    }
+
+    pub fn touch_user(&self, tg_user_id: u64, timestamp: u64) -> Result<usize> {
+        self.conn.execute(
+            "UPDATE users SET last_seen = ?2 WHERE tg_user_id = ?1",
+            params![tg_user_id as i64, timestamp as i64],
+        )
+    }
+
+    pub fn update_tg_username(&self, tg_user_id: u64, tg_username: Option<&str>) -> Result<usize> {
+        let tg_username = tg_username.map(|x| x.to_lowercase());
+        let updated = self.conn.execute(
+            "UPDATE users SET tg_username = ?2 WHERE tg_user_id = ?1 AND tg_username IS NOT ?2",
+            params![tg_user_id as i64, tg_username],
+        )?;
+
+        // usernames can move between accounts
+        if updated > 0 && tg_username.is_some() {
+            self.conn.execute(
+                "UPDATE users SET tg_username = NULL WHERE tg_username = ?2 AND tg_user_id != ?1",
+                params![tg_user_id as i64, tg_username],
+            )?;
+        }
+
+        Ok(updated)
     }
 
     pub fn upsert_user(&self, user: &User) -> Result<usize> {
-        self.conn.execute("INSERT INTO users (tg_user_id, account_username, api_type, profile_shown, cover_shown) VALUES (?1, ?2, ?3, ?4, ?5) ON CONFLICT (tg_user_id) DO UPDATE SET account_username = ?2, api_type = ?3, profile_shown = ?4, cover_shown = ?5",
-         params![user.tg_user_id as i64, user.account_username, user.api_type, user.profile_shown, user.cover_shown])
+        self.conn.execute("INSERT INTO users (tg_user_id, account_username, api_type, profile_shown, cover_shown, notify_milestones, timezone, number_locale, language, personality, broadcasts, analytics) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12) ON CONFLICT (tg_user_id) DO UPDATE SET account_username = ?2, api_type = ?3, profile_shown = ?4, cover_shown = ?5, notify_milestones = ?6, timezone = ?7, number_locale = ?8, language = ?9, personality = ?10, broadcasts = ?11, analytics = ?12",
+         params![user.tg_user_id as i64, user.account_username, user.api_type, user.profile_shown, user.cover_shown, user.notify_milestones, user.timezone, user.number_locale, user.language, user.personality, user.broadcasts, user.analytics])
+    }
+
+    // only the account, the user's preferences stay as they are
+    pub fn set_account(
+        &self,
+        tg_user_id: u64,
+        account_username: &str,
+        api_type: &ApiType,
+    ) -> Result<usize> {
+        self.conn.execute(
+            "INSERT INTO users (tg_user_id, account_username, api_type) VALUES (?1, ?2, ?3) ON CONFLICT (tg_user_id) DO UPDATE SET account_username = ?2, api_type = ?3",
+            params![tg_user_id as i64, account_username, api_type.to_string()],
+        )
+    }
+
+    pub fn set_analytics(&self, tg_user_id: u64, enabled: bool) -> Result<()> {
+        self.conn.execute(
+            "UPDATE users SET analytics = ?2 WHERE tg_user_id = ?1",
+            params![tg_user_id as i64, enabled],
+        )?;
+        if enabled {
+            self.conn.execute(
+                "DELETE FROM analytics_opt_outs WHERE tg_user_id = ?1",
+                [tg_user_id as i64],
+            )?;
+        } else {
+            self.conn.execute(
+                "INSERT OR IGNORE INTO analytics_opt_outs (tg_user_id) VALUES (?1)",
+                [tg_user_id as i64],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn analytics_opted_out(&self, tg_user_id: u64) -> bool {
+        self.conn
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM analytics_opt_outs WHERE tg_user_id = ?1)
+                OR EXISTS (SELECT 1 FROM users WHERE tg_user_id = ?1 AND analytics = 0)",
+                [tg_user_id as i64],
+                |row| row.get(0),
+            )
+            .unwrap_or(false)
+    }
+
+    // the stored value, or this one if there is none yet
+    pub fn fetch_or_insert_meta(&self, key: &str, value: &str) -> Result<String> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO meta (key, value) VALUES (?1, ?2)",
+            params![key, value],
+        )?;
+        self.conn
+            .query_row("SELECT value FROM meta WHERE key = ?1", [key], |row| {
+                row.get(0)
+            })
     }
 
     pub fn delete_user(&self, tg_user_id: u64) -> Result<usize> {
+        self.conn.execute(
+            "DELETE FROM milestones WHERE tg_user_id = ?1",
+            [tg_user_id as i64],
+        )?;
         self.conn.execute(
             "DELETE FROM users WHERE tg_user_id = ?1",
             [tg_user_id as i64],
         )
     }
+
+    pub fn upsert_chat_member(
+        &self,
+        chat_id: i64,
+        tg_user_id: u64,
+        last_seen: u64,
+    ) -> Result<usize> {
+        self.conn.execute(
+            "INSERT INTO chat_members (chat_id, tg_user_id, last_seen) VALUES (?1, ?2, ?3) ON CONFLICT (chat_id, tg_user_id) DO UPDATE SET last_seen = ?3",
+            params![chat_id, tg_user_id as i64, last_seen as i64],
+        )
+    }
+
+    pub fn delete_chat_member(&self, chat_id: i64, tg_user_id: u64) -> Result<usize> {
+        self.conn.execute(
+            "DELETE FROM chat_members WHERE chat_id = ?1 AND tg_user_id = ?2",
+            params![chat_id, tg_user_id as i64],
+        )
+    }
+
+    pub fn is_chat_member(&self, chat_id: i64, tg_user_id: u64) -> bool {
+        self.conn
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM chat_members WHERE chat_id = ?1 AND tg_user_id = ?2)",
+                params![chat_id, tg_user_id as i64],
+                |row| row.get(0),
+            )
+            .unwrap_or(false)
+    }
+
+    // registered members of a chat, most recently seen first
+    pub fn fetch_chat_members(&self, chat_id: i64, limit: usize) -> Vec<User> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {USER_COLUMNS} FROM chat_members JOIN users ON users.tg_user_id = chat_members.tg_user_id WHERE chat_members.chat_id = ?1 ORDER BY chat_members.last_seen DESC LIMIT ?2"
+            ))
+            .unwrap();
+
+        stmt.query_map(params![chat_id, limit as i64], user_from_row)
+            .unwrap()
+            .filter_map(|x| x.ok())
+            .collect()
+    }
+
+    // chats the user is in that want milestone announcements
+    pub fn fetch_milestone_chats(&self, tg_user_id: u64) -> Vec<i64> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT chat_members.chat_id FROM chat_members JOIN milestone_chats ON milestone_chats.chat_id = chat_members.chat_id WHERE chat_members.tg_user_id = ?1",
+            )
+            .unwrap();
+
+        stmt.query_map([tg_user_id as i64], |row| row.get(0))
+            .unwrap()
+            .filter_map(|x| x.ok())
+            .collect()
+    }
+
+    pub fn is_milestone_chat(&self, chat_id: i64) -> bool {
+        self.conn
+            .query_row(
+                "SELECT 1 FROM milestone_chats WHERE chat_id = ?1",
+                [chat_id],
+                |_| Ok(()),
+            )
+            .is_ok()
+    }
+
+    pub fn set_milestone_chat(&self, chat_id: i64, enabled: bool) -> Result<usize> {
+        if enabled {
+            self.conn.execute(
+                "INSERT OR IGNORE INTO milestone_chats (chat_id) VALUES (?1)",
+                [chat_id],
+            )
+        } else {
+            self.conn
+                .execute("DELETE FROM milestone_chats WHERE chat_id = ?1", [chat_id])
+        }
+    }
+
+    pub fn fetch_chat_personality(&self, chat_id: i64) -> Option<Personality> {
+        self.conn
+            .query_row(
+                "SELECT personality FROM chats WHERE chat_id = ?1",
+                [chat_id],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .ok()
+            .flatten()
+            .and_then(|x| x.parse().ok())
+    }
+
+    // None goes back to each user's own choice
+    pub fn set_chat_personality(
+        &self,
+        chat_id: i64,
+        personality: Option<Personality>,
+    ) -> Result<usize> {
+        self.conn.execute(
+            "INSERT INTO chats (chat_id, personality) VALUES (?1, ?2) ON CONFLICT (chat_id) DO UPDATE SET personality = ?2",
+            params![chat_id, personality.map(|x| x.to_string())],
+        )
+    }
+
+    // chats that never changed anything get the defaults
+    pub fn fetch_chat_settings(&self, chat_id: i64) -> ChatSettings {
+        self.conn
+            .query_row(
+                "SELECT chat_id, disabled_commands, clean_collages, allowed_topics, slashless, default_period FROM chats WHERE chat_id = ?1",
+                [chat_id],
+                |row| {
+                    Ok(ChatSettings {
+                        chat_id: row.get(0)?,
+                        disabled_commands: row.get(1)?,
+                        clean_collages: row.get(2)?,
+                        allowed_topics: row.get(3)?,
+                        slashless: row.get(4)?,
+                        default_period: row.get(5)?,
+                    })
+                },
+            )
+            .unwrap_or_else(|_| ChatSettings::new(chat_id))
+    }
+
+    // leaves the personality alone, that has its own command
+    pub fn upsert_chat_settings(&self, settings: &ChatSettings) -> Result<usize> {
+        self.conn.execute(
+            "INSERT INTO chats (chat_id, disabled_commands, clean_collages, allowed_topics, slashless, default_period) VALUES (?1, ?2, ?3, ?4, ?5, ?6) ON CONFLICT (chat_id) DO UPDATE SET disabled_commands = ?2, clean_collages = ?3, allowed_topics = ?4, slashless = ?5, default_period = ?6",
+            params![
+                settings.chat_id,
+                settings.disabled_commands,
+                settings.clean_collages,
+                settings.allowed_topics,
+                settings.slashless,
+                settings.default_period
+            ],
+        )
+    }
+
+    // (account username, scrobble milestone, artist milestone)
+    pub fn fetch_milestones(&self, tg_user_id: u64) -> Option<(String, u64, u64)> {
+        self.conn
+            .query_row(
+                "SELECT account_username, scrobbles, artists FROM milestones WHERE tg_user_id = ?1",
+                [tg_user_id as i64],
+                |row| {
+                    Ok((
+                        row.get(0)?,
+                        row.get::<_, i64>(1)? as u64,
+                        row.get::<_, i64>(2)? as u64,
+                    ))
+                },
+            )
+            .ok()
+    }
+
+    pub fn upsert_milestones(
+        &self,
+        tg_user_id: u64,
+        account_username: &str,
+        scrobbles: u64,
+        artists: u64,
+    ) -> Result<usize> {
+        self.conn.execute(
+            "INSERT INTO milestones (tg_user_id, account_username, scrobbles, artists) VALUES (?1, ?2, ?3, ?4) ON CONFLICT (tg_user_id) DO UPDATE SET account_username = ?2, scrobbles = ?3, artists = ?4",
+            params![tg_user_id as i64, account_username, scrobbles as i64, artists as i64],
+        )
+    }
+
+    pub fn insert_event(
+        &self,
+        time: u64,
+        event_type: &str,
+        user_id: Option<&str>,
+        language: Option<&str>,
+    ) -> Result<usize> {
+        self.conn.execute(
+            "INSERT INTO events (time, event_type, user_id, language) VALUES (?1, ?2, ?3, ?4)",
+            params![time as i64, event_type, user_id, language],
+        )
+    }
+
+    // (event type, count) since the given unix milliseconds, most common first
+    pub fn count_events(&self, since: u64) -> Vec<(String, u64)> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT event_type, COUNT(*) FROM events WHERE time >= ?1 GROUP BY event_type ORDER BY COUNT(*) DESC, event_type")
+            .unwrap();
+
+        stmt.query_map([since as i64], |row| {
+            Ok((row.get(0)?, row.get::<_, i64>(1)? as u64))
+        })
+        .unwrap()
+        .filter_map(|x| x.ok())
+        .collect()
+    }
+
+    // (utc date, events, distinct users) since the given unix milliseconds
+    pub fn count_events_by_day(&self, since: u64) -> Vec<(String, u64, u64)> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT date(time / 1000, 'unixepoch') AS day, COUNT(*), COUNT(DISTINCT user_id) FROM events WHERE time >= ?1 GROUP BY day ORDER BY day")
+            .unwrap();
+
+        stmt.query_map([since as i64], |row| {
+            Ok((
+                row.get(0)?,
+                row.get::<_, i64>(1)? as u64,
+                row.get::<_, i64>(2)? as u64,
+            ))
+        })
+        .unwrap()
+        .filter_map(|x| x.ok())
+        .collect()
+    }
+
+    pub fn store_callback_payload(&self, key: &str, payload: &[u8], now: u64) -> Result<usize> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO callback_payloads (key, payload, created_at) VALUES (?1, ?2, ?3)",
+            params![key, payload, now as i64],
+        )
+    }
+
+    pub fn delete_callback_payloads_before(&self, time: u64) -> Result<usize> {
+        self.conn.execute(
+            "DELETE FROM callback_payloads WHERE created_at < ?1",
+            [time as i64],
+        )
+    }
+
+    pub fn fetch_callback_payload(&self, key: &str) -> Option<Vec<u8>> {
+        self.conn
+            .query_row(
+                "SELECT payload FROM callback_payloads WHERE key = ?1",
+                [key],
+                |row| row.get(0),
+            )
+            .ok()
+    }
 }
@@ -0,0 +1,221 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt, fs,
+    sync::{LazyLock, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use reqwest::Url;
+use serde::Serialize;
+use teloxide::prelude::Requester;
+
+use crate::{
+    Bot, DB,
+    api_requester::{self, ApiType},
+    config,
+};
+
+const PROBE_INTERVAL: Duration = Duration::from_secs(60);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+// consecutive failures of a service before commands stop waiting for it
+const BREAKER_THRESHOLD: u32 = 5;
+const BREAKER_COOLDOWN: Duration = Duration::from_secs(60);
+// the bot can't work at all without these, the scrobbling services only degrade some commands
+const ESSENTIAL: [&str; 2] = ["telegram", "sqlite"];
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct Probe {
+    pub ok: bool,
+    pub latency_ms: u64,
+    pub last_error: Option<String>,
+    // unix seconds
+    pub last_error_at: Option<u64>,
+}
+
+// the newest result per component, what /health reports
+static PROBES: LazyLock<Mutex<BTreeMap<String, Probe>>> = LazyLock::new(Default::default);
+
+// without the middleware stack, a 404 still means the service is up
+static PROBE_CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
+    reqwest::ClientBuilder::new()
+        .timeout(PROBE_TIMEOUT)
+        .user_agent("LastFM Robot (Telegram bot)")
+        .build()
+        .unwrap()
+});
+
+#[derive(Default)]
+struct Breaker {
+    failures: u32,
+    open_until: Option<Instant>,
+}
+
+// indexed by ApiType
+static BREAKERS: LazyLock<Mutex<[Breaker; 3]>> = LazyLock::new(Default::default);
+
+// how long requests to this service keep failing fast, None when it's let through
+pub fn breaker_open_for(api_type: ApiType) -> Option<Duration> {
+    let breakers = BREAKERS.lock().unwrap();
+    breakers[api_type as usize]
+        .open_until
+        .and_then(|x| x.checked_duration_since(Instant::now()))
+}
+
+// after the cooldown one request goes through, another failure opens it again right away
+pub fn record_upstream(api_type: ApiType, ok: bool) {
+    let mut breakers = BREAKERS.lock().unwrap();
+    let breaker = &mut breakers[api_type as usize];
+    if ok {
+        *breaker = Breaker::default();
+        return;
+    }
+    breaker.failures += 1;
+    if breaker.failures >= BREAKER_THRESHOLD {
+        if breaker.open_until.is_none_or(|x| x <= Instant::now()) {
+            log::warn!("{api_type} is failing, pausing requests to it");
+        }
+        breaker.open_until = Some(Instant::now() + BREAKER_COOLDOWN);
+    }
+}
+
+// what requests fail with while the breaker is open, displays as the message id users are shown
+#[derive(Debug)]
+pub struct UpstreamDown(pub ApiType);
+
+impl fmt::Display for UpstreamDown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self.0 {
+            ApiType::Lastfm => "lastfm-down",
+            ApiType::Librefm => "librefm-down",
+            ApiType::Listenbrainz => "listenbrainz-down",
+        })
+    }
+}
+
+impl Error for UpstreamDown {}
+
+// whether a handler failed because a breaker was open
+pub fn upstream_down<'a>(e: &'a (dyn Error + 'static)) -> Option<&'a UpstreamDown> {
+    match e.downcast_ref::<reqwest_middleware::Error>()? {
+        reqwest_middleware::Error::Middleware(e) => e.downcast_ref::<UpstreamDown>(),
+        _ => None,
+    }
+}
+
+// cheap calls that every instance of the service answers
+async fn probe_upstream(api_type: ApiType) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let base_url = api_requester::get_base_url(&api_type);
+    let url = match api_type {
+        ApiType::Lastfm | ApiType::Librefm => Url::parse_with_params(
+            base_url,
+            &[
+                ("method", "chart.getTopArtists"),
+                ("limit", "1"),
+                ("api_key", config::LASTFM_API_KEY),
+                ("format", "json"),
+            ],
+        )?,
+        ApiType::Listenbrainz => Url::parse_with_params(
+            &format!("{base_url}stats/sitewide/artists"),
+            &[("count", "1")],
+        )?,
+    };
+
+    let status = PROBE_CLIENT.get(url).send().await?.status();
+    if status.is_server_error() {
+        return Err(Box::from(status.to_string()));
+    }
+    Ok(())
+}
+
+async fn probe(
+    name: &str,
+    check: impl Future<Output = Result<(), Box<dyn Error + Send + Sync>>>,
+) -> Probe {
+    let started = Instant::now();
+    let result = check.await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let mut probes = PROBES.lock().unwrap();
+    let previous = probes.remove(name).unwrap_or_default();
+    let probe = match result {
+        Ok(()) => Probe {
+            ok: true,
+            latency_ms,
+            ..previous
+        },
+        Err(e) => Probe {
+            ok: false,
+            latency_ms,
+            last_error: Some(e.to_string()),
+            last_error_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|x| x.as_secs()),
+        },
+    };
+    probes.insert(name.to_owned(), probe.clone());
+    probe
+}
+
+// runs every probe now, upstream results also feed the breakers
+pub async fn check_all(bot: &Bot) -> BTreeMap<String, Probe> {
+    for api_type in (0..).map_while(ApiType::from_repr) {
+        let result = probe(&api_type.to_string(), probe_upstream(api_type)).await;
+        record_upstream(api_type, result.ok);
+    }
+
+    probe("telegram", async {
+        bot.get_me().await?;
+        Ok(())
+    })
+    .await;
+
+    probe("sqlite", async {
+        DB.lock().unwrap().ping()?;
+        Ok(())
+    })
+    .await;
+
+    probe("font", async {
+        match fs::metadata(config::FONT_FILE_PATH) {
+            Ok(x) if x.is_file() => Ok(()),
+            Ok(_) => Err(Box::from(format!(
+                "{} is not a file",
+                config::FONT_FILE_PATH
+            ))),
+            Err(e) => Err(Box::from(format!("{}: {e}", config::FONT_FILE_PATH))),
+        }
+    })
+    .await;
+
+    PROBES.lock().unwrap().clone()
+}
+
+pub async fn run_prober(bot: Bot) {
+    let mut interval = tokio::time::interval(PROBE_INTERVAL);
+    loop {
+        interval.tick().await;
+        check_all(&bot).await;
+    }
+}
+
+// (healthy, json body) from the last round of probes
+pub fn report() -> (bool, String) {
+    let probes = PROBES.lock().unwrap();
+    let healthy = ESSENTIAL
+        .iter()
+        .all(|x| probes.get(*x).is_some_and(|x| x.ok));
+    let degraded = probes
+        .iter()
+        .filter(|(name, probe)| !probe.ok && !ESSENTIAL.contains(&name.as_str()))
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    let body = serde_json::json!({
+        "healthy": healthy,
+        "degraded": degraded,
+        "components": *probes,
+    });
+    (healthy, body.to_string())
+}
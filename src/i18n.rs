@@ -0,0 +1,304 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{LazyLock, Mutex, RwLock},
+};
+
+use fluent_bundle::{FluentArgs, FluentResource, concurrent::FluentBundle};
+use strum_macros::{Display, EnumString, IntoStaticStr};
+use teloxide::types::ChatId;
+use unic_langid::LanguageIdentifier;
+
+use crate::{
+    DB,
+    api_requester::{EntryType, TimePeriod},
+    db,
+};
+
+pub const DEFAULT_LANG: &str = "en";
+pub const DEFAULT_PERSONALITY: Personality = Personality::Uwu;
+const LOCALES_DIR: &str = "locales";
+
+// locales/<lang>/*.ftl is the plain voice, locales/<lang>/<personality>/*.ftl overrides parts of it
+#[derive(Debug, Clone, Copy, PartialEq, EnumString, Display, IntoStaticStr)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum Personality {
+    Uwu,
+    Plain,
+}
+
+pub const PERSONALITIES: [Personality; 2] = [Personality::Uwu, Personality::Plain];
+
+type Bundle = FluentBundle<FluentResource>;
+
+// keyed by "<lang>" for the plain voice and "<lang>/<personality>" for the others
+static CATALOGUES: LazyLock<RwLock<HashMap<&'static str, Bundle>>> =
+    LazyLock::new(|| RwLock::new(load_catalogues().expect("locales directory is missing")));
+// voices are handed out as &'static str, so their keys have to outlive a /reloadlocales
+static KEYS: LazyLock<Mutex<HashSet<&'static str>>> = LazyLock::new(Default::default);
+
+fn intern(key: String) -> &'static str {
+    let mut keys = KEYS.lock().unwrap();
+    match keys.get(key.as_str()) {
+        Some(key) => key,
+        None => {
+            let key: &'static str = Box::leak(key.into_boxed_str());
+            keys.insert(key);
+            key
+        }
+    }
+}
+
+fn ftl_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = fs::read_dir(dir)
+        .map(|x| {
+            x.filter_map(|x| x.ok())
+                .map(|x| x.path())
+                .filter(|x| x.extension().is_some_and(|ext| ext == "ftl"))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+// later directories override messages of the earlier ones
+fn load_bundle(lang: &str, dirs: &[&Path]) -> Option<Bundle> {
+    let langid = lang.parse::<LanguageIdentifier>().ok()?;
+    let mut bundle = Bundle::new_concurrent(vec![langid]);
+    // the isolation marks would end up inside telegram html
+    bundle.set_use_isolating(false);
+
+    for (i, dir) in dirs.iter().enumerate() {
+        for file in ftl_files(dir) {
+            let source = match fs::read_to_string(&file) {
+                Ok(source) => source,
+                Err(e) => {
+                    log::error!("can't read {}: {e}", file.display());
+                    continue;
+                }
+            };
+            let resource = FluentResource::try_new(source).unwrap_or_else(|(resource, errors)| {
+                log::error!("{}: {errors:?}", file.display());
+                resource
+            });
+            if i > 0 {
+                bundle.add_resource_overriding(resource);
+            } else if let Err(errors) = bundle.add_resource(resource) {
+                log::error!("{}: {errors:?}", file.display());
+            }
+        }
+    }
+    Some(bundle)
+}
+
+fn load_catalogues() -> io::Result<HashMap<&'static str, Bundle>> {
+    let mut catalogues = HashMap::new();
+    let entries = fs::read_dir(LOCALES_DIR)?;
+    for entry in entries.filter_map(|x| x.ok()) {
+        let path = entry.path();
+        let Some(lang) = path.file_name().and_then(|x| x.to_str()).map(str::to_owned) else {
+            continue;
+        };
+        if !path.is_dir() {
+            continue;
+        }
+        let Some(bundle) = load_bundle(&lang, &[&path]) else {
+            log::error!("invalid locale directory {lang}");
+            continue;
+        };
+        catalogues.insert(intern(lang.clone()), bundle);
+
+        let overlays = fs::read_dir(&path)
+            .into_iter()
+            .flatten()
+            .filter_map(|x| x.ok())
+            .map(|x| x.path())
+            .filter(|x| x.is_dir());
+        for overlay in overlays {
+            let Some(personality) = overlay
+                .file_name()
+                .and_then(|x| x.to_str())
+                .and_then(|x| x.parse::<Personality>().ok())
+            else {
+                log::error!("unknown personality {}", overlay.display());
+                continue;
+            };
+            if let Some(bundle) = load_bundle(&lang, &[&path, &overlay]) {
+                catalogues.insert(intern(voice_key(&lang, personality)), bundle);
+            }
+        }
+    }
+    Ok(catalogues)
+}
+
+// picks up edited .ftl files without a restart, returns the number of voices
+pub fn reload() -> io::Result<usize> {
+    let catalogues = load_catalogues()?;
+    let count = catalogues.len();
+    *CATALOGUES.write().unwrap() = catalogues;
+    Ok(count)
+}
+
+fn voice_key(lang: &str, personality: Personality) -> String {
+    match personality {
+        Personality::Plain => lang.to_owned(),
+        _ => format!("{lang}/{personality}"),
+    }
+}
+
+pub fn languages() -> Vec<&'static str> {
+    let mut langs = CATALOGUES
+        .read()
+        .unwrap()
+        .keys()
+        .copied()
+        .filter(|x| !x.contains('/'))
+        .collect::<Vec<_>>();
+    langs.sort();
+    langs
+}
+
+// "pt-BR" falls back to "pt", anything unknown to None
+pub fn supported(code: &str) -> Option<&'static str> {
+    let code = code.to_lowercase();
+    let catalogues = CATALOGUES.read().unwrap();
+    [
+        code.as_str(),
+        code.split(['-', '_']).next().unwrap_or_default(),
+    ]
+    .into_iter()
+    .filter(|x| !x.contains('/'))
+    .find_map(|x| catalogues.get_key_value(x).map(|(k, _)| *k))
+}
+
+// a language without that personality speaks plainly
+pub fn voice(lang: &'static str, personality: Personality) -> &'static str {
+    CATALOGUES
+        .read()
+        .unwrap()
+        .get_key_value(voice_key(lang, personality).as_str())
+        .map(|(k, _)| *k)
+        .unwrap_or(lang)
+}
+
+// the language part of a voice
+pub fn language(voice: &str) -> &str {
+    voice.split('/').next().unwrap_or(voice)
+}
+
+// a group's voice, picked by its admins, wins over the user's
+pub fn personality(user: Option<&db::User>, chat_id: Option<ChatId>) -> Personality {
+    chat_id
+        .and_then(|x| DB.lock().unwrap().fetch_chat_personality(x.0))
+        .or_else(|| user.and_then(|x| x.personality()))
+        .unwrap_or(DEFAULT_PERSONALITY)
+}
+
+// the stored preference wins over the telegram client's language
+pub fn lang(
+    tg_user: &teloxide::types::User,
+    user: Option<&db::User>,
+    chat_id: Option<ChatId>,
+) -> &'static str {
+    let lang = user
+        .and_then(|x| x.language())
+        .and_then(supported)
+        .or_else(|| tg_user.language_code.as_deref().and_then(supported))
+        .unwrap_or(DEFAULT_LANG);
+    voice(lang, personality(user, chat_id))
+}
+
+fn format(lang: &str, id: &str, args: Option<&FluentArgs>) -> Option<String> {
+    let catalogues = CATALOGUES.read().unwrap();
+    let bundle = catalogues.get(lang)?;
+    let pattern = bundle.get_message(id)?.value()?;
+    let mut errors = vec![];
+    let text = bundle.format_pattern(pattern, args, &mut errors);
+    if !errors.is_empty() {
+        log::warn!("{lang} {id}: {errors:?}");
+    }
+    Some(text.into_owned())
+}
+
+// falls back to english in the same voice, then to plain english
+fn lookup(lang: &str, id: &str, args: Option<&FluentArgs>) -> Option<String> {
+    let fallback = match lang.split_once('/') {
+        Some((_, personality)) => format!("{DEFAULT_LANG}/{personality}"),
+        None => DEFAULT_LANG.to_owned(),
+    };
+    format(lang, id, args)
+        .or_else(|| format(&fallback, id, args))
+        .or_else(|| format(DEFAULT_LANG, id, args))
+}
+
+pub fn t_args(lang: &str, id: &str, args: Option<&FluentArgs>) -> String {
+    lookup(lang, id, args).unwrap_or_else(|| {
+        log::error!("missing message {id}");
+        id.to_owned()
+    })
+}
+
+pub fn t(lang: &str, id: &str) -> String {
+    t_args(lang, id, None)
+}
+
+// api errors carry a message id or the http reason
+pub fn middleware_error(lang: &str, e: &reqwest_middleware::Error) -> String {
+    let Some(source) = std::error::Error::source(e).map(|x| x.to_string()) else {
+        return t(lang, "err-msg");
+    };
+    lookup(lang, &source, None).unwrap_or(source)
+}
+
+pub fn period(lang: &str, period: &TimePeriod) -> String {
+    let id = match period {
+        TimePeriod::OneWeek => "period-one-week",
+        TimePeriod::OneMonth => "period-one-month",
+        TimePeriod::ThreeMonths => "period-three-months",
+        TimePeriod::SixMonths => "period-six-months",
+        TimePeriod::OneYear => "period-one-year",
+        TimePeriod::AllTime => "period-all-time",
+    };
+    t(lang, id)
+}
+
+pub fn entry(lang: &str, entry_type: &EntryType) -> String {
+    t(lang, &format!("entry-{entry_type}"))
+}
+
+pub fn entries(lang: &str, entry_type: &EntryType) -> String {
+    t(lang, &format!("entries-{entry_type}"))
+}
+
+// day 0 is monday
+pub fn weekday(lang: &str, day: usize) -> String {
+    t(lang, &format!("weekday-{day}"))
+}
+
+pub fn weekday_short(lang: &str, day: usize) -> String {
+    t(lang, &format!("weekday-short-{day}"))
+}
+
+// month 1 is january
+pub fn month(lang: &str, month: u32) -> String {
+    t(lang, &format!("month-{month}"))
+}
+
+pub fn month_short(lang: &str, month: u32) -> String {
+    t(lang, &format!("month-short-{month}"))
+}
+
+// tr!(lang, "flex-streak", days = 5), values are passed as preformatted strings
+#[macro_export]
+macro_rules! tr {
+    ($lang:expr, $id:expr) => {
+        $crate::i18n::t($lang, $id)
+    };
+    ($lang:expr, $id:expr, $($name:ident = $value:expr),+ $(,)?) => {{
+        let mut args = fluent_bundle::FluentArgs::new();
+        $(args.set(stringify!($name), $value.to_string());)+
+        $crate::i18n::t_args($lang, $id, Some(&args))
+    }};
+}
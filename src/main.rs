@@ -1,100 +1,209 @@
 use std::{
-    cmp::min,
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     error::Error,
     fs::File,
     io::{BufRead, BufReader},
     sync::{LazyLock, Mutex, OnceLock},
+    time::Instant,
 };
 
 use api_requester::{ApiType, TimePeriod};
-use db::{Db, User};
+use chrono::{DateTime, Datelike, NaiveDate, Timelike, Utc};
+use db::{ChatSettings, Db, User};
 use num_format::{Locale, ToFormattedString};
 use rand::seq::IndexedRandom;
 use reqwest::Url;
-use strum_macros::{Display, EnumString, IntoStaticStr};
+use strum_macros::IntoStaticStr;
 use teloxide::{
     adaptors::{Throttle, throttle::Limits},
     payloads::SendMessageSetters,
     prelude::*,
     types::{
-        BotCommand, InlineKeyboardButton, InlineKeyboardMarkup, InlineQueryResult,
-        InlineQueryResultArticle, InlineQueryResultsButton, InlineQueryResultsButtonKind,
-        InputFile, InputMediaPhoto, InputMessageContent, InputMessageContentText, Me,
-        MessageEntityKind, ParseMode, ReplyParameters,
+        BotCommand, BotCommandScope, ChatAction, InlineKeyboardButton, InlineKeyboardMarkup,
+        InlineQueryResult, InlineQueryResultArticle, InlineQueryResultsButton,
+        InlineQueryResultsButtonKind, InputFile, InputMediaDocument, InputMediaPhoto,
+        InputMessageContent, InputMessageContentText, Me, MessageEntityKind, ParseMode, Recipient,
+        ReplyParameters,
     },
     utils::command::BotCommands,
 };
 use tokio::task;
 use utils::choose_the_from;
 
-use crate::api_requester::EntryType;
+use crate::{
+    api_requester::EntryType,
+    callback::{Callback, ChatSettingsAction, Payload, PreferenceAction, StatusType},
+    chart::ChartEntry,
+    collage::{Captions, CollageStyle, OutputFormat, Theme},
+};
 mod anal;
 mod api_requester;
+mod callback;
+mod card;
+mod chart;
 mod collage;
+mod compat;
 mod config;
 mod consts;
 mod db;
+mod health;
+mod i18n;
+mod metrics;
+mod milestones;
 mod utils;
+mod wrapped;
 
 type Bot = Throttle<teloxide::Bot>;
 
-#[derive(BotCommands, Clone)]
-#[command(
-    rename_rule = "lowercase",
-    description = "These commands are supported:"
-)]
+#[derive(BotCommands, Clone, IntoStaticStr)]
+// descriptions live in the cmd-* messages of the catalogue
+#[command(rename_rule = "lowercase")]
+#[strum(serialize_all = "lowercase")]
 enum Command {
-    #[command(description = "Vrooooooom!")]
     Start,
-    #[command(description = "Your last played song")]
     Status,
+    #[strum(serialize = "status")]
     Np,
-    #[command(description = "Your last 3 songs and album art")]
     #[allow(non_camel_case_types)]
     Status_Full,
+    #[strum(serialize = "status_full")]
     NpFull,
-    #[command(description = "Your last 5 loved tracks")]
     Loved,
-    #[command(description = "Your compatibility score")]
     Compat {
         arg: String,
     },
-    #[command(description = "Create album collage")]
+    Tastematrix {
+        arg: String,
+    },
     Collage {
         arg: String,
     },
-    #[command(description = "A random top artist/album/track")]
     Random {
         arg: String,
     },
-    #[command(description = "Top 5 artists/albums/tracks as text")]
     Topkek {
         arg: String,
     },
-    #[command(description = "Flewx your nuwmbers")]
     Flex,
-    #[command(description = "Set your username")]
+    Milestones,
+    Personality {
+        arg: String,
+    },
+    ChatSettings {
+        arg: String,
+    },
+    Clock {
+        arg: String,
+    },
+    Genres {
+        arg: String,
+    },
+    Wrapped {
+        arg: String,
+    },
+    Plays {
+        arg: String,
+    },
+    Artist {
+        arg: String,
+    },
+    Album {
+        arg: String,
+    },
+    Track {
+        arg: String,
+    },
     Set {
         arg: String,
     },
-    #[command(description = "Your pwefewences for this bot")]
-    Preferences,
-    #[command(description = "Weeeeelp!")]
+    Preferences {
+        arg: String,
+    },
     Help,
-    #[command(description = "Priwacy powicy")]
-    Privacy,
+    Privacy {
+        arg: String,
+    },
+    // owner only, not in the command menu
+    Stats {
+        arg: String,
+    },
+    Broadcast {
+        arg: String,
+    },
+    Lookup {
+        arg: String,
+    },
+    ReloadLocales,
+    Cache {
+        arg: String,
+    },
+    Upstreams,
 }
 
+const DEFAULT_TOP_COUNT: usize = 5;
+const MAX_TOP_COUNT: usize = 30;
+// telegram's limit for photos, bigger ones go as files
+const MAX_PHOTO_BYTES: usize = 10 * 1024 * 1024;
+const GENRES_ARTIST_COUNT: usize = 30;
+const GENRES_SHOWN: usize = 10;
+const COMPAT_ARTISTS_SHOWN: usize = 8;
+const TASTEMATRIX_MAX_MEMBERS: usize = 12;
+// 200 scrobbles per lastfm page, 1000 per listenbrainz page
+const WRAPPED_MAX_PAGES: usize = 150;
+const CLOCK_MAX_PAGES: usize = 50;
+
 static DB: LazyLock<Mutex<Db>> = LazyLock::new(|| Mutex::new(Db::new()));
 static ME: OnceLock<Me> = OnceLock::new();
+static ACCEPTABLE_TAGS: LazyLock<HashSet<String>> = LazyLock::new(|| {
+    BufReader::new(File::open("everynoise_genres.txt").unwrap())
+        .lines()
+        .map(|x| x.unwrap())
+        .collect()
+});
+// command -> catalogue message
 static COMMAND_USAGE_MAP: LazyLock<HashMap<String, &str>> = LazyLock::new(|| {
     let mut h = HashMap::new();
-    h.insert("collage".to_string(), consts::COLLAGE_USAGE);
-    h.insert("topkek".to_string(), consts::TOP_USAGE);
-    h.insert("random".to_string(), consts::RANDOM_USAGE);
+    h.insert("collage".to_string(), "collage-usage");
+    h.insert("topkek".to_string(), "top-usage");
+    h.insert("random".to_string(), "random-usage");
     h
 });
+// shown in the telegram command menu and /help, in this order
+const VISIBLE_COMMANDS: &[&str] = &[
+    "status",
+    "status_full",
+    "loved",
+    "collage",
+    "compat",
+    "tastematrix",
+    "random",
+    "topkek",
+    "flex",
+    "milestones",
+    "personality",
+    "chatsettings",
+    "clock",
+    "genres",
+    "wrapped",
+    "plays",
+    "artist",
+    "album",
+    "track",
+    "preferences",
+    "help",
+    "privacy",
+];
+// edit the /broadcast progress message after this many sends
+const BROADCAST_PROGRESS_EVERY: usize = 25;
+
+// group admins can't turn these off in /chatsettings
+const LOCKED_COMMANDS: &[&str] = &[
+    "personality",
+    "chatsettings",
+    "preferences",
+    "help",
+    "privacy",
+];
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
@@ -108,56 +217,129 @@ async fn main() -> Result<(), Box<dyn Error>> {
     });
 
     let handler = dptree::entry()
-        .branch(Update::filter_message().endpoint(message_handler))
-        .branch(Update::filter_callback_query().endpoint(callback_handler))
-        .branch(Update::filter_inline_query().endpoint(inline_query_handler))
-        .branch(Update::filter_my_chat_member().endpoint(my_chat_member_handler))
-        .branch(Update::filter_chosen_inline_result().endpoint(inline_result_handler));
-
-    let visible_commands: HashSet<&str> = vec![
-        "status",
-        "status_full",
-        "loved",
-        "collage",
-        "compat",
-        "random",
-        "topkek",
-        "flex",
-        "preferences",
-        "help",
-        "privacy",
-    ]
-    .into_iter()
-    .collect();
-
-    bot.send_message(config::OWNER_ID.to_string(), consts::BOT_STARTED)
-        .await?;
+        .branch(Update::filter_message().endpoint(|bot: Bot, msg: Message| {
+            metrics::timed("message", async move {
+                let result = message_handler(bot.clone(), msg.clone()).await;
+                if let Some(from) = msg.from.as_ref() {
+                    let lang = lang_of(from, Some(msg.chat.id));
+                    upstream_down_reply(&bot, Some(&msg), None, false, lang, &result).await;
+                }
+                result
+            })
+        }))
+        .branch(
+            Update::filter_callback_query().endpoint(|bot: Bot, q: CallbackQuery| {
+                metrics::timed("callback", async move {
+                    let result = callback_handler(bot.clone(), q.clone()).await;
+                    let msg = q.regular_message();
+                    let lang = lang_of(&q.from, msg.map(|x| x.chat.id));
+                    let inline_message_id = q.inline_message_id.clone();
+                    upstream_down_reply(&bot, msg, inline_message_id, true, lang, &result).await;
+                    result
+                })
+            }),
+        )
+        .branch(
+            Update::filter_inline_query().endpoint(|bot: Bot, q: InlineQuery| {
+                metrics::timed("inline_query", inline_query_handler(bot, q))
+            }),
+        )
+        .branch(Update::filter_my_chat_member().endpoint(
+            |bot: Bot, me: Me, update: ChatMemberUpdated| {
+                metrics::timed("my_chat_member", my_chat_member_handler(bot, me, update))
+            },
+        ))
+        .branch(Update::filter_chosen_inline_result().endpoint(
+            |bot: Bot, result: ChosenInlineResult| {
+                metrics::timed("chosen_inline_result", inline_result_handler(bot, result))
+            },
+        ));
+
+    bot.send_message(
+        config::OWNER_ID.to_string(),
+        tr!(
+            i18n::voice(i18n::DEFAULT_LANG, i18n::DEFAULT_PERSONALITY),
+            "bot-started"
+        ),
+    )
+    .await?;
     ME.set(bot.get_me().await?).unwrap();
 
-    let commands: Vec<BotCommand> = Command::bot_commands()
-        .iter()
-        .filter(|bc| visible_commands.contains(&bc.command[1..])) // because it includes the /
-        .cloned()
-        .collect();
+    set_commands(&bot).await?;
 
-    bot.set_my_commands(commands).await?;
+    tokio::spawn(milestones::run_notifier(bot.clone()));
+    tokio::spawn(anal::run_flusher());
+    tokio::spawn(metrics::serve());
+    tokio::spawn(health::run_prober(bot.clone()));
 
     Dispatcher::builder(bot, handler)
         .enable_ctrlc_handler()
         .build()
         .dispatch()
         .await;
+
+    // dispatch returns once ctrl-c stopped it, keep what is still buffered
+    anal::flush().await;
+    Ok(())
+}
+
+// the default list, plus one per language for clients set to it
+async fn set_commands(bot: &Bot) -> Result<(), teloxide::RequestError> {
+    bot.set_my_commands(bot_commands(i18n::voice(
+        i18n::DEFAULT_LANG,
+        i18n::DEFAULT_PERSONALITY,
+    )))
+    .await?;
+    for lang in i18n::languages() {
+        bot.set_my_commands(bot_commands(i18n::voice(lang, i18n::DEFAULT_PERSONALITY)))
+            .language_code(lang)
+            .await?;
+    }
     Ok(())
 }
 
+fn bot_commands(lang: &str) -> Vec<BotCommand> {
+    VISIBLE_COMMANDS
+        .iter()
+        .map(|command| BotCommand::new(*command, tr!(lang, &format!("cmd-{command}"))))
+        .collect()
+}
+
+fn help_text(lang: &str) -> String {
+    let lines = VISIBLE_COMMANDS
+        .iter()
+        .map(|command| format!("/{command} — {}", tr!(lang, &format!("cmd-{command}"))))
+        .collect::<Vec<_>>();
+    format!("{}\n\n{}", tr!(lang, "help-header"), lines.join("\n"))
+}
+
+// only describes what this instance actually does with the data
+fn privacy_text(lang: &str, opted_out: bool) -> String {
+    let mut parts = vec![
+        tr!(lang, "privacy-stored"),
+        tr!(lang, "privacy-groups"),
+        tr!(lang, "privacy-analytics"),
+    ];
+    if anal::amplitude_enabled() {
+        parts.push(tr!(lang, "privacy-amplitude"));
+    }
+    parts.push(if opted_out {
+        tr!(lang, "privacy-opted-out")
+    } else {
+        tr!(lang, "privacy-opt-out")
+    });
+    parts.push(tr!(lang, "privacy-delete"));
+    parts.join("\n\n")
+}
+
 async fn track(event_type: &str, user: Option<&teloxide::types::User>) {
+    metrics::inc("commands_total", &[("command", event_type)]);
     anal::add_event(
         event_type,
         user,
         ME.get().unwrap().username.clone().unwrap(),
     )
-    .await
-    .unwrap_or_default();
+    .await;
 }
 
 async fn message_handler(bot: Bot, msg: Message) -> Result<(), Box<dyn Error + Send + Sync>> {
@@ -170,15 +352,30 @@ async fn message_handler(bot: Bot, msg: Message) -> Result<(), Box<dyn Error + S
         }
 
         if from.unwrap().is_anonymous() {
-            utils::send_or_edit_message(&bot, consts::ANON_KUN, None, None, false, None, true)
-                .await?;
+            utils::send_or_edit_message(
+                &bot,
+                &tr!(
+                    i18n::voice(
+                        i18n::DEFAULT_LANG,
+                        i18n::personality(None, Some(msg.chat.id))
+                    ),
+                    "anon-kun"
+                ),
+                None,
+                None,
+                false,
+                None,
+                true,
+            )
+            .await?;
             return Ok(());
         }
 
         let mut parsed_command = BotCommands::parse(text, ME.get().unwrap().username());
+        let settings = DB.lock().unwrap().fetch_chat_settings(msg.chat.id.0);
 
         // commands without a /
-        if parsed_command.is_err() {
+        if parsed_command.is_err() && settings.slashless {
             let splits: Vec<_> = text.splitn(2, ' ').map(|x| x.to_lowercase()).collect();
             let first_word = splits.first().map(|x| x.as_str()); //.cloned();
             let second_word = splits.get(1).cloned();
@@ -202,27 +399,80 @@ async fn message_handler(bot: Bot, msg: Message) -> Result<(), Box<dyn Error + S
             }
         }
 
+        // admins can always reach /chatsettings to undo a restriction
+        if let Ok(command) = &parsed_command {
+            let name: &'static str = command.into();
+            if name != "chatsettings"
+                && (settings.is_disabled(name) || !settings.allows_topic(topic_id(&msg)))
+            {
+                return Ok(());
+            }
+        }
+
         let user: User;
         match parsed_command {
             Ok(Command::Start) => {
-                start_command(&bot, msg.chat.id).await?;
+                start_command(&bot, msg.chat.id, from.unwrap()).await?;
                 track("start", from).await;
                 return Ok(());
             }
             Ok(Command::Help) => {
-                bot.send_message(msg.chat.id, Command::descriptions().to_string())
+                let lang = lang_of(from.unwrap(), Some(msg.chat.id));
+                bot.send_message(msg.chat.id, help_text(lang))
                     .reply_parameters(ReplyParameters::new(msg.id).allow_sending_without_reply())
                     .await?;
                 track("help", from).await;
                 return Ok(());
             }
             Ok(Command::Set { arg }) => {
-                set_command(&bot, &msg, None, &arg, false).await?;
+                let (username, api_type) = arg.split_once(' ').unwrap_or((&arg, ""));
+                let api_type = api_type.parse().unwrap_or(ApiType::Lastfm);
+                set_command(&bot, &msg, None, username, api_type, false).await?;
                 track("set", from).await;
                 return Ok(());
             }
-            Ok(Command::Privacy) => {
-                bot.send_message(msg.chat.id, consts::PRIVACY_POLICY)
+            Ok(Command::Milestones) => {
+                milestones_command(&bot, &msg).await?;
+                track("milestones", from).await;
+                return Ok(());
+            }
+            Ok(Command::Personality { arg }) => {
+                personality_command(&bot, &msg, &arg).await?;
+                track("personality", from).await;
+                return Ok(());
+            }
+            Ok(Command::ChatSettings { arg }) => {
+                let action = parse_chatsettings_arg(&arg);
+                chatsettings_command(&bot, &msg, from.unwrap(), action, false).await?;
+                track("chatsettings", from).await;
+                return Ok(());
+            }
+            Ok(
+                Command::Stats { .. }
+                | Command::Broadcast { .. }
+                | Command::Lookup { .. }
+                | Command::ReloadLocales
+                | Command::Cache { .. }
+                | Command::Upstreams,
+            ) => {
+                // everyone else gets no hint that these exist
+                if is_owner(from.unwrap()) {
+                    owner_command(&bot, &msg, parsed_command.unwrap()).await?;
+                }
+                return Ok(());
+            }
+            Ok(Command::Privacy { arg }) => {
+                let tg_user_id = from.unwrap().id.0;
+                let user = DB.lock().unwrap().fetch_user(tg_user_id);
+                let lang = i18n::lang(from.unwrap(), user.as_ref(), Some(msg.chat.id));
+                // works without registering, unlike the toggle in /preferences
+                match arg.trim() {
+                    "on" => DB.lock().unwrap().set_analytics(tg_user_id, true)?,
+                    "off" => DB.lock().unwrap().set_analytics(tg_user_id, false)?,
+                    _ => (),
+                }
+                let opted_out = DB.lock().unwrap().analytics_opted_out(tg_user_id);
+                bot.send_message(msg.chat.id, privacy_text(lang, opted_out))
                     .reply_parameters(ReplyParameters::new(msg.id).allow_sending_without_reply())
                     .await?;
                 track("privacy", from).await;
@@ -231,6 +481,17 @@ async fn message_handler(bot: Bot, msg: Message) -> Result<(), Box<dyn Error + S
             Ok(_) => {
                 let u = get_registered_user(&bot, Some(&msg), None, None, false).await;
                 if let Ok(u) = u {
+                    let db = DB.lock().unwrap();
+                    let _ = db.update_tg_username(u.tg_user_id, from.unwrap().username.as_deref());
+                    let _ = db.touch_user(u.tg_user_id, msg.date.timestamp() as u64);
+                    if !msg.chat.is_private() {
+                        let _ = db.upsert_chat_member(
+                            msg.chat.id.0,
+                            u.tg_user_id,
+                            msg.date.timestamp() as u64,
+                        );
+                    }
+                    drop(db);
                     user = u;
                 } else {
                     return Ok(());
@@ -274,35 +535,115 @@ async fn message_handler(bot: Bot, msg: Message) -> Result<(), Box<dyn Error + S
                 loved_command(&bot, Some(&msg), None, None, false, user).await?;
                 track("loved", from).await;
             }
-            Ok(Command::Preferences) => {
-                preferences_command(&bot, Some(&msg), None, None, false, "", user).await?;
+            Ok(Command::Preferences { arg }) => {
+                let arg = arg.trim();
+                match arg.parse::<PreferenceAction>() {
+                    Ok(action) => {
+                        preferences_command(&bot, Some(&msg), None, None, false, Some(action), user)
+                            .await?
+                    }
+                    Err(_) => set_preference(&bot, &msg, arg, user).await?,
+                }
                 track("preferences", from).await;
             }
             Ok(Command::Collage { arg }) => {
                 if arg.is_empty() {
-                    period_chooser(&bot, Some(&msg), None, None, false, "collage").await?;
+                    match settings.default_period() {
+                        Some(period) => {
+                            size_chooser(
+                                &bot,
+                                Some(&msg),
+                                None,
+                                None,
+                                false,
+                                Payload::collage().with_period(period),
+                            )
+                            .await?
+                        }
+                        None => {
+                            period_chooser(&bot, Some(&msg), None, None, false, Payload::collage())
+                                .await?
+                        }
+                    }
                 } else {
-                    collage_command(&bot, Some(&msg), None, None, false, &arg, user).await?;
+                    let (size, period, _, style) = utils::parse_collage_arg(&arg);
+                    collage_command(
+                        &bot,
+                        Some(&msg),
+                        None,
+                        None,
+                        false,
+                        period,
+                        size,
+                        style,
+                        user,
+                    )
+                    .await?;
                 }
                 track("collage", from).await;
             }
             Ok(Command::Topkek { arg }) => {
                 if arg.is_empty() {
-                    type_chooser(&bot, Some(&msg), None, None, false, "topkek").await?;
+                    type_chooser(
+                        &bot,
+                        Some(&msg),
+                        None,
+                        None,
+                        false,
+                        with_default_period(Payload::topkek(DEFAULT_TOP_COUNT), &settings),
+                    )
+                    .await?;
                 } else {
-                    topkek_command(&bot, Some(&msg), None, None, false, &arg, user).await?;
+                    let (entry_type, period, as_chart, count, page) = parse_topkek_arg(&arg);
+                    topkek_command(
+                        &bot,
+                        Some(&msg),
+                        None,
+                        None,
+                        false,
+                        entry_type,
+                        period,
+                        as_chart,
+                        count,
+                        page,
+                        user,
+                    )
+                    .await?;
                 }
                 track("topkek", from).await;
             }
             Ok(Command::Compat { arg }) => {
-                compat_command(&bot, &msg, &arg, user).await?;
+                compat_command(&bot, Some(&msg), None, None, false, &arg, user).await?;
                 track("compat", from).await;
             }
+            Ok(Command::Tastematrix { arg }) => {
+                tastematrix_command(&bot, &msg, &arg).await?;
+                track("tastematrix", from).await;
+            }
             Ok(Command::Random { arg }) => {
                 if arg.is_empty() {
-                    type_chooser(&bot, Some(&msg), None, None, false, "random").await?;
+                    type_chooser(
+                        &bot,
+                        Some(&msg),
+                        None,
+                        None,
+                        false,
+                        with_default_period(Payload::random(), &settings),
+                    )
+                    .await?;
                 } else {
-                    random_command(&bot, Some(&msg), None, None, false, &arg, user).await?;
+                    let (_, period, entry_type, _) = utils::parse_collage_arg(&arg);
+                    random_command(
+                        &bot,
+                        Some(&msg),
+                        None,
+                        None,
+                        false,
+                        entry_type,
+                        period,
+                        user,
+                    )
+                    .await?;
                 }
                 track("random", from).await;
             }
@@ -310,6 +651,66 @@ async fn message_handler(bot: Bot, msg: Message) -> Result<(), Box<dyn Error + S
                 flex_command(&bot, Some(&msg), None, None, false, user).await?;
                 track("flex", from).await;
             }
+            Ok(Command::Clock { arg }) => {
+                let arg = default_period_arg(arg, &settings);
+                clock_command(&bot, &msg, &arg, user).await?;
+                track("clock", from).await;
+            }
+            Ok(Command::Genres { arg }) => {
+                let arg = default_period_arg(arg, &settings);
+                genres_command(&bot, Some(&msg), None, None, false, &arg, user).await?;
+                track("genres", from).await;
+            }
+            Ok(Command::Wrapped { arg }) => {
+                wrapped_command(&bot, &msg, &arg, user).await?;
+                track("wrapped", from).await;
+            }
+            Ok(Command::Plays { arg }) => {
+                entity_command(&bot, Some(&msg), None, None, false, None, &arg, user).await?;
+                track("plays", from).await;
+            }
+            Ok(Command::Artist { arg }) => {
+                entity_command(
+                    &bot,
+                    Some(&msg),
+                    None,
+                    None,
+                    false,
+                    Some(EntryType::Artist),
+                    &arg,
+                    user,
+                )
+                .await?;
+                track("artist", from).await;
+            }
+            Ok(Command::Album { arg }) => {
+                entity_command(
+                    &bot,
+                    Some(&msg),
+                    None,
+                    None,
+                    false,
+                    Some(EntryType::Album),
+                    &arg,
+                    user,
+                )
+                .await?;
+                track("album", from).await;
+            }
+            Ok(Command::Track { arg }) => {
+                entity_command(
+                    &bot,
+                    Some(&msg),
+                    None,
+                    None,
+                    false,
+                    Some(EntryType::Track),
+                    &arg,
+                    user,
+                )
+                .await?;
+                track("track", from).await;
+            }
 
             Err(_) => {}
 
@@ -320,6 +721,38 @@ async fn message_handler(bot: Bot, msg: Message) -> Result<(), Box<dyn Error + S
     Ok(())
 }
 
+fn callback_button(
+    text: impl Into<String>,
+    allowed_user_id: u64,
+    payload: Payload,
+) -> InlineKeyboardButton {
+    let data = Callback::new(allowed_user_id, payload).encode(&DB.lock().unwrap());
+    InlineKeyboardButton::callback(text, data)
+}
+
+// general and non forum chats count as topic 1, like telegram's own general topic
+fn topic_id(msg: &Message) -> i32 {
+    match msg.thread_id {
+        Some(thread_id) if msg.is_topic_message => thread_id.0.0,
+        _ => 1,
+    }
+}
+
+fn with_default_period(payload: Payload, settings: &ChatSettings) -> Payload {
+    match settings.default_period() {
+        Some(period) => payload.with_period(period),
+        None => payload,
+    }
+}
+
+// a bare command in a chat with a default period acts as if it was typed
+fn default_period_arg(arg: String, settings: &ChatSettings) -> String {
+    match settings.default_period() {
+        Some(period) if arg.trim().is_empty() => callback::period_arg(period).to_string(),
+        _ => arg,
+    }
+}
+
 async fn get_registered_user(
     bot: &Bot,
     msg: Option<&Message>,
@@ -334,17 +767,13 @@ async fn get_registered_user(
         Some(user) => Ok(user),
 
         None => {
-            utils::send_or_edit_message(
-                bot,
-                consts::NOT_REGISTERED,
-                msg,
-                inline_message_id,
-                edit,
-                None,
-                true,
-            )
-            .await?;
-            Err(Box::from(consts::NOT_REGISTERED))
+            let text = tr!(
+                i18n::lang(&from, None, msg.map(|x| x.chat.id)),
+                "not-registered"
+            );
+            utils::send_or_edit_message(bot, &text, msg, inline_message_id, edit, None, true)
+                .await?;
+            Err(Box::from(text))
         }
     }
 }
@@ -354,16 +783,14 @@ async fn send_err_msg(
     msg: Option<&Message>,
     inline_message_id: Option<String>,
     edit: bool,
+    lang: &str,
     e: Box<dyn Error + Send + Sync>,
 ) {
     log::error!("{e}");
     let text = if let Some(middleware_error) = e.downcast_ref::<reqwest_middleware::Error>() {
-        middleware_error
-            .source()
-            .map(|e| e.to_string())
-            .unwrap_or(consts::ERR_MSG.to_string())
+        i18n::middleware_error(lang, middleware_error)
     } else {
-        consts::ERR_MSG.to_string()
+        tr!(lang, "err-msg")
     };
 
     utils::send_or_edit_message(bot, text.as_str(), msg, inline_message_id, edit, None, true)
@@ -371,32 +798,65 @@ async fn send_err_msg(
         .unwrap_or_default();
 }
 
+// commands that ran into an open circuit breaker with ? still say so, the others already replied
+async fn upstream_down_reply(
+    bot: &Bot,
+    msg: Option<&Message>,
+    inline_message_id: Option<String>,
+    edit: bool,
+    lang: &str,
+    result: &Result<(), Box<dyn Error + Send + Sync>>,
+) {
+    let Some(down) = result
+        .as_ref()
+        .err()
+        .and_then(|e| health::upstream_down(e.as_ref()))
+    else {
+        return;
+    };
+
+    utils::send_or_edit_message(
+        bot,
+        &tr!(lang, &down.to_string()),
+        msg,
+        inline_message_id,
+        edit,
+        None,
+        true,
+    )
+    .await
+    .unwrap_or_default();
+}
+
 async fn my_chat_member_handler(
     bot: Bot,
     me: Me,
     chat_member_updated: ChatMemberUpdated,
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
     if chat_member_updated.new_chat_member.user.id == me.id {
-        start_command(&bot, chat_member_updated.chat.id).await?;
+        start_command(&bot, chat_member_updated.chat.id, &chat_member_updated.from).await?;
     }
     Ok(())
 }
 
-async fn start_command(bot: &Bot, chat_id: ChatId) -> Result<(), Box<dyn Error + Send + Sync>> {
-    bot.send_message(chat_id, consts::WELCOME_TEXT)
-        .parse_mode(ParseMode::Html)
-        .await?;
+async fn start_command(
+    bot: &Bot,
+    chat_id: ChatId,
+    from: &teloxide::types::User,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let db_user = DB.lock().unwrap().fetch_user(from.id.0);
+    bot.send_message(
+        chat_id,
+        tr!(
+            i18n::lang(from, db_user.as_ref(), Some(chat_id)),
+            "welcome-text"
+        ),
+    )
+    .parse_mode(ParseMode::Html)
+    .await?;
     Ok(())
 }
 
-#[derive(Debug, PartialEq, Display, EnumString, IntoStaticStr)]
-#[strum(serialize_all = "snake_case")]
-enum StatusType {
-    Compact,
-    CompactWithCover,
-    Expanded,
-}
-
 #[allow(clippy::too_many_arguments)]
 async fn status_command(
     bot: &Bot,
@@ -408,14 +868,9 @@ async fn status_command(
     prefer_cached: bool,
     user: User,
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
-    static ACCEPTABLE_TAGS: LazyLock<HashSet<String>> = LazyLock::new(|| {
-        BufReader::new(File::open("everynoise_genres.txt").unwrap())
-            .lines()
-            .map(|x| x.unwrap())
-            .collect()
-    });
-
+    let locale = user.locale();
     let from = utils::choose_the_from(msg, inline_from);
+    let lang = i18n::lang(&from, Some(&user), msg.map(|x| x.chat.id));
 
     let status_type = if status_type_param == StatusType::Compact && user.cover_shown {
         StatusType::CompactWithCover
@@ -445,8 +900,8 @@ async fn status_command(
     match tracks {
         Ok(tracks) => {
             if tracks.is_empty() {
-                let text = consts::NO_SCROBBLES;
-                utils::send_or_edit_message(bot, text, msg, inline_message_id, edit, None, true)
+                let text = tr!(lang, "no-scrobbles");
+                utils::send_or_edit_message(bot, &text, msg, inline_message_id, edit, None, true)
                     .await?;
 
                 return Ok(());
@@ -466,27 +921,23 @@ async fn status_command(
 
                 if let Ok(track_info) = track_info {
                     user_playcount = track_info.user_playcount;
-                    tags_text = track_info
-                        .tags
-                        .unwrap_or_default()
-                        .iter()
-                        .map(|t| t.to_lowercase())
-                        .filter(|t| t.split(' ').any(|x| ACCEPTABLE_TAGS.contains(x)))
-                        .map(|t| {
-                            t.replace(
-                                &['(', ')', ',', '\"', '.', ';', ':', '\'', '-', ' ', '/'][..],
-                                "_",
-                            )
-                        })
-                        .filter(|x| !x.is_empty())
-                        .map(|x| format!("#{x}"))
-                        .collect::<Vec<_>>()
-                        .join(" ");
+                    tags_text = utils::hashtags(
+                        track_info
+                            .tags
+                            .unwrap_or_default()
+                            .iter()
+                            .map(|t| t.to_lowercase())
+                            .filter(|t| is_genre_tag(t)),
+                    );
                 }
             }
 
             let mut first_track_info = if user_playcount > 0 {
-                format!(", {user_playcount} plays")
+                tr!(
+                    lang,
+                    "status-plays",
+                    plays = user_playcount.to_formatted_string(&locale)
+                )
             } else {
                 "".to_owned()
             };
@@ -523,7 +974,11 @@ async fn status_command(
                             .map(|x| format!(", [{}]", utils::replace_html_symbols(x)))
                             .unwrap_or("".to_string()),
                         time_ago,
-                        if track.user_loved { ", 💗 loved" } else { "" },
+                        if track.user_loved {
+                            tr!(lang, "status-loved")
+                        } else {
+                            String::new()
+                        },
                         first_track_info,
                     );
 
@@ -533,14 +988,14 @@ async fn status_command(
                 .collect::<Vec<String>>()
                 .join("\n");
 
+            let heading = if tracks[0].now_playing {
+                "status-now-listening"
+            } else {
+                "status-was-listening"
+            };
             let text = format!(
-                "{} {} listening to\n{}{}",
-                utils::name_with_link(&from, &user),
-                if tracks[0].now_playing {
-                    "is now"
-                } else {
-                    "was"
-                },
+                "{}\n{}{}",
+                tr!(lang, heading, name = utils::name_with_link(&from, &user)),
                 tracks_text,
                 first_track_info,
             );
@@ -549,21 +1004,24 @@ async fn status_command(
 
             match status_type {
                 StatusType::Expanded => {
-                    keyboard[0].push(InlineKeyboardButton::callback(
+                    keyboard[0].push(callback_button(
                         "➖",
-                        format!("{} status {}", from.id.0, StatusType::CompactWithCover),
+                        from.id.0,
+                        Payload::Status(StatusType::CompactWithCover),
                     ));
                 }
                 StatusType::Compact => {
                     if tracks[0].album_art_url.is_some() {
-                        keyboard[0].push(InlineKeyboardButton::callback(
+                        keyboard[0].push(callback_button(
                             "🖼️",
-                            format!("{} status {}", from.id.0, StatusType::CompactWithCover),
+                            from.id.0,
+                            Payload::Status(StatusType::CompactWithCover),
                         ));
                     }
-                    keyboard[0].push(InlineKeyboardButton::callback(
+                    keyboard[0].push(callback_button(
                         "➕",
-                        format!("{} status {}", from.id.0, StatusType::Expanded),
+                        from.id.0,
+                        Payload::Status(StatusType::Expanded),
                     ));
                 }
                 StatusType::CompactWithCover => {
@@ -571,23 +1029,79 @@ async fn status_command(
                     //     "➖",
                     //     format!("{} status {}", from.id.0, StatusType::Compact),
                     // ));
-                    keyboard[0].push(InlineKeyboardButton::callback(
+                    keyboard[0].push(callback_button(
+                        "➕",
+                        from.id.0,
+                        Payload::Status(StatusType::Expanded),
+                    ));
+                }
+                StatusType::Card => {
+                    keyboard[0].push(callback_button(
+                        "➖",
+                        from.id.0,
+                        Payload::Status(StatusType::CompactWithCover),
+                    ));
+                    keyboard[0].push(callback_button(
                         "➕",
-                        format!("{} status {}", from.id.0, StatusType::Expanded),
+                        from.id.0,
+                        Payload::Status(StatusType::Expanded),
                     ));
                 }
             }
 
-            if inline_message_id.is_none() {
-                keyboard[0].push(InlineKeyboardButton::callback("ℹ️", "0 info"));
+            if status_type != StatusType::Card {
+                keyboard[0].push(callback_button(
+                    "🪪",
+                    from.id.0,
+                    Payload::Status(StatusType::Card),
+                ));
             }
 
-            keyboard[0].push(InlineKeyboardButton::callback(
+            keyboard[0].push(callback_button(
+                "ℹ️",
+                0,
+                Payload::Info {
+                    artist: tracks[0].artist.clone(),
+                    title: tracks[0].name.clone(),
+                },
+            ));
+
+            keyboard[0].push(callback_button(
                 "🔃",
-                format!("{} status_refresh {}", from.id.0, status_type),
+                from.id.0,
+                Payload::StatusRefresh(status_type),
             ));
 
-            if ((status_type == StatusType::CompactWithCover
+            if status_type == StatusType::Card {
+                match card::create_now_playing_card(
+                    &tracks[0],
+                    user_playcount,
+                    &from.first_name,
+                    &locale,
+                    lang,
+                )
+                .await
+                {
+                    Ok(card) => {
+                        utils::send_or_edit_photo(
+                            bot,
+                            InputMediaPhoto::new(InputFile::memory(card))
+                                .caption(text)
+                                .show_caption_above_media(true),
+                            msg,
+                            inline_message_id.as_ref(),
+                            edit,
+                            Some(InlineKeyboardMarkup::new(keyboard)),
+                            true,
+                        )
+                        .await?;
+                    }
+                    Err(e) => {
+                        log::error!("card generator failed {e}");
+                        send_err_msg(bot, msg, inline_message_id, edit, lang, e.into()).await;
+                    }
+                }
+            } else if ((status_type == StatusType::CompactWithCover
                 || status_type == StatusType::Expanded)
                 && album_art_url.is_some())
                 || msg_is_photo
@@ -621,7 +1135,7 @@ async fn status_command(
         }
 
         Err(e) => {
-            send_err_msg(bot, msg, inline_message_id, edit, e).await;
+            send_err_msg(bot, msg, inline_message_id, edit, lang, e).await;
         }
     }
 
@@ -637,6 +1151,7 @@ async fn loved_command(
     user: User,
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
     let from = utils::choose_the_from(msg, inline_from);
+    let lang = i18n::lang(&from, Some(&user), msg.map(|x| x.chat.id));
 
     let tracks =
         api_requester::fetch_loved_tracks(user.account_username.as_str(), &user.api_type()).await;
@@ -644,8 +1159,8 @@ async fn loved_command(
     match tracks {
         Ok(tracks) => {
             if tracks.is_empty() {
-                let text = consts::NO_SCROBBLES;
-                utils::send_or_edit_message(bot, text, msg, inline_message_id, edit, None, true)
+                let text = tr!(lang, "no-scrobbles");
+                utils::send_or_edit_message(bot, &text, msg, inline_message_id, edit, None, true)
                     .await?;
 
                 return Ok(());
@@ -678,8 +1193,12 @@ async fn loved_command(
                 .join("\n");
 
             let text = format!(
-                "{}'s loved tracks:\n{}",
-                utils::name_with_link(&from, &user),
+                "{}\n{}",
+                tr!(
+                    lang,
+                    "loved-heading",
+                    name = utils::name_with_link(&from, &user)
+                ),
                 tracks_text,
             );
 
@@ -688,7 +1207,7 @@ async fn loved_command(
         }
 
         Err(e) => {
-            send_err_msg(bot, msg, inline_message_id, edit, e).await;
+            send_err_msg(bot, msg, inline_message_id, edit, lang, e).await;
         }
     }
 
@@ -699,32 +1218,41 @@ async fn set_command(
     bot: &Bot,
     msg: &Message,
     inline_from: Option<&teloxide::types::User>,
-    arg: &str,
+    username: &str,
+    api_type: ApiType,
     edit: bool,
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
-    if arg.is_empty() {
-        utils::send_or_edit_message(bot, consts::SET_CLICK, msg.into(), None, edit, None, true)
-            .await?;
-        return Ok(());
-    }
-
     let from = choose_the_from(msg.into(), inline_from);
+    let db_user = DB.lock().unwrap().fetch_user(from.id.0);
+    let lang = i18n::lang(&from, db_user.as_ref(), Some(msg.chat.id));
 
-    let arg_splits = arg.splitn(2, ' ').collect::<Vec<_>>();
-
-    let username = arg_splits[0];
-    let api_type_str = arg_splits.get(1).cloned().unwrap_or_default();
-    let api_type = api_type_str.parse().unwrap_or(ApiType::Lastfm);
-
-    let recent_tracks = api_requester::fetch_recent_tracks(username, &api_type, false, 1).await;
-
-    let buttons = [ApiType::Lastfm, ApiType::Listenbrainz, ApiType::Librefm]
+    if username.is_empty() {
+        utils::send_or_edit_message(
+            bot,
+            &tr!(lang, "set-click"),
+            msg.into(),
+            None,
+            edit,
+            None,
+            true,
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let recent_tracks = api_requester::fetch_recent_tracks(username, &api_type, false, 1).await;
+
+    let buttons = [ApiType::Lastfm, ApiType::Listenbrainz, ApiType::Librefm]
         .iter()
         .filter(|&x| x != &api_type)
         .map(|x| {
-            InlineKeyboardButton::callback(
+            callback_button(
                 x.to_string(),
-                format!("{} set {} {}", from.id.0, username, x),
+                from.id.0,
+                Payload::Set {
+                    username: username.to_string(),
+                    api_type: *x,
+                },
             )
         })
         .collect::<Vec<_>>();
@@ -733,28 +1261,27 @@ async fn set_command(
 
     let text = match recent_tracks {
         Ok(_) => {
-            let new_user = db::User::new(from.id.0, username.to_owned(), &api_type, false, false);
-
-            DB.lock().unwrap().upsert_user(&new_user)?;
-            format!(
-                "✅Username set for {api_type}!\n\nUse /preferences to show links to your {api_type} profile, or always show album art for status if available.\n\nNot {api_type}? Change your account type using the buttons."
-            )
+            DB.lock()
+                .unwrap()
+                .set_account(from.id.0, username, &api_type)?;
+            let _ = DB
+                .lock()
+                .unwrap()
+                .update_tg_username(from.id.0, from.username.as_deref());
+            tr!(lang, "set-done", service = api_type)
         }
 
         Err(e) => {
             log::error!("{e}");
             if let Some(middleware_error) = e.downcast_ref::<reqwest_middleware::Error>() {
-                format!(
-                    "{}\n\n{} for {}\n\nChange your account type using the buttons.",
-                    middleware_error
-                        .source()
-                        .map(|e| e.to_string())
-                        .unwrap_or(consts::ERR_MSG.to_string()),
-                    consts::USER_NOT_FOUND,
-                    api_type
+                tr!(
+                    lang,
+                    "set-failed",
+                    error = i18n::middleware_error(lang, middleware_error),
+                    service = api_type
                 )
             } else {
-                consts::ERR_MSG.to_string()
+                tr!(lang, "err-msg")
             }
         }
     };
@@ -764,87 +1291,250 @@ async fn set_command(
     Ok(())
 }
 
+// the preferences that take a value, typed as /preferences timezone Europe/Berlin
+async fn set_preference(
+    bot: &Bot,
+    msg: &Message,
+    arg: &str,
+    user: User,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let from = msg.from.as_ref().unwrap();
+    let mut user = user;
+    let lang = i18n::lang(from, Some(&user), Some(msg.chat.id));
+    let mut words = arg.split_whitespace();
+    let name = words.next().unwrap_or_default();
+    let value = words.next();
+
+    let usage = match name {
+        // auto goes back to the default
+        "personality" => match value {
+            Some("auto") => {
+                user.set_personality(None);
+                None
+            }
+            Some(name) if name.parse::<i18n::Personality>().is_ok() => {
+                user.set_personality(name.parse().ok());
+                None
+            }
+            _ => Some(tr!(
+                lang,
+                "personality-usage",
+                personalities = personality_names()
+            )),
+        },
+        "timezone" | "tz" => match value.and_then(utils::parse_timezone) {
+            Some(timezone) => {
+                user.set_timezone(timezone);
+                None
+            }
+            None => Some(tr!(lang, "timezone-usage")),
+        },
+        "locale" | "numbers" => match value.and_then(utils::parse_locale) {
+            Some(locale) => {
+                user.set_locale(locale);
+                None
+            }
+            None => Some(tr!(lang, "locale-usage")),
+        },
+        // auto follows the telegram app
+        "language" | "lang" => match value {
+            Some("auto") => {
+                user.set_language(None);
+                None
+            }
+            Some(code) if i18n::supported(code).is_some() => {
+                user.set_language(i18n::supported(code));
+                None
+            }
+            _ => Some(tr!(
+                lang,
+                "language-usage",
+                languages = i18n::languages().join(", ")
+            )),
+        },
+        _ => None,
+    };
+
+    if let Some(usage) = usage {
+        utils::send_or_edit_message(bot, &usage, msg.into(), None, false, None, true).await?;
+        return Ok(());
+    }
+
+    if !name.is_empty() {
+        DB.lock().unwrap().upsert_user(&user)?;
+    }
+    preferences_command(bot, Some(msg), None, None, false, None, user).await
+}
+
 async fn preferences_command(
     bot: &Bot,
     msg: Option<&Message>,
     inline_message_id: Option<String>,
     inline_from: Option<&teloxide::types::User>,
     edit: bool,
-    arg: &str,
+    action: Option<PreferenceAction>,
     user: User,
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
     let from = utils::choose_the_from(msg, inline_from);
     let mut user = user;
-    match arg {
-        "profile_show" => {
-            user.profile_shown = true;
-            DB.lock().unwrap().upsert_user(&user)?;
-        }
-        "profile_hide" => {
-            user.profile_shown = false;
-            DB.lock().unwrap().upsert_user(&user)?;
-        }
-        "cover_show" => {
-            user.cover_shown = true;
-            DB.lock().unwrap().upsert_user(&user)?;
-        }
-        "cover_hide" => {
-            user.cover_shown = false;
-            DB.lock().unwrap().upsert_user(&user)?;
+    let lang = i18n::lang(&from, Some(&user), msg.map(|x| x.chat.id));
+    let Some(action) = action else {
+        return preferences_menu(bot, msg, inline_message_id, edit, &from, user).await;
+    };
+
+    match action {
+        PreferenceAction::ProfileShow => user.profile_shown = true,
+        PreferenceAction::ProfileHide => user.profile_shown = false,
+        PreferenceAction::CoverShow => user.cover_shown = true,
+        PreferenceAction::CoverHide => user.cover_shown = false,
+        PreferenceAction::MilestonesOn => user.notify_milestones = true,
+        PreferenceAction::MilestonesOff => user.notify_milestones = false,
+        PreferenceAction::BroadcastsOn => user.broadcasts = true,
+        PreferenceAction::BroadcastsOff => user.broadcasts = false,
+        PreferenceAction::AnalyticsOn | PreferenceAction::AnalyticsOff => {
+            user.analytics = action == PreferenceAction::AnalyticsOn;
+            DB.lock()
+                .unwrap()
+                .set_analytics(user.tg_user_id, user.analytics)?;
         }
-        "unset" => {
+        PreferenceAction::PersonalityUwu => user.set_personality(Some(i18n::Personality::Uwu)),
+        PreferenceAction::PersonalityPlain => user.set_personality(Some(i18n::Personality::Plain)),
+        PreferenceAction::Unset => {
             DB.lock().unwrap().delete_user(user.tg_user_id).unwrap();
-            utils::send_or_edit_message(bot, consts::UNSET, msg, None, true, None, true).await?;
+            utils::send_or_edit_message(bot, &tr!(lang, "unset"), msg, None, true, None, true)
+                .await?;
             return Ok(());
         }
-        _ => {}
     }
 
+    DB.lock().unwrap().upsert_user(&user)?;
+    preferences_menu(bot, msg, inline_message_id, edit, &from, user).await
+}
+
+async fn preferences_menu(
+    bot: &Bot,
+    msg: Option<&Message>,
+    inline_message_id: Option<String>,
+    edit: bool,
+    from: &teloxide::types::User,
+    user: User,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    // the language or personality may have just changed
+    let lang = i18n::lang(from, Some(&user), msg.map(|x| x.chat.id));
+    // also covers users who opted out with /privacy before registering
+    let analytics = !DB.lock().unwrap().analytics_opted_out(user.tg_user_id);
+    let personality = user.personality().unwrap_or(i18n::DEFAULT_PERSONALITY);
     let mut buttons = vec![];
 
-    buttons.push(InlineKeyboardButton::callback(
+    buttons.push(callback_button(
         format!(
-            "{} Profile links",
-            if user.profile_shown { "✅" } else { "⬜" }
+            "{} {}",
+            if user.profile_shown { "✅" } else { "⬜" },
+            tr!(lang, "pref-profile-links")
         ),
+        from.id.0,
+        Payload::Preferences(if user.profile_shown {
+            PreferenceAction::ProfileHide
+        } else {
+            PreferenceAction::ProfileShow
+        }),
+    ));
+
+    buttons.push(callback_button(
         format!(
-            "{} preferences {}",
-            from.id,
-            if user.profile_shown {
-                "profile_hide"
-            } else {
-                "profile_show"
-            }
+            "{} {}",
+            if user.cover_shown { "✅" } else { "⬜" },
+            tr!(lang, "pref-album-art")
+        ),
+        from.id.0,
+        Payload::Preferences(if user.cover_shown {
+            PreferenceAction::CoverHide
+        } else {
+            PreferenceAction::CoverShow
+        }),
+    ));
+
+    buttons.push(callback_button(
+        format!(
+            "{} {}",
+            if user.notify_milestones { "✅" } else { "⬜" },
+            tr!(lang, "pref-milestones")
         ),
+        from.id.0,
+        Payload::Preferences(if user.notify_milestones {
+            PreferenceAction::MilestonesOff
+        } else {
+            PreferenceAction::MilestonesOn
+        }),
     ));
 
-    buttons.push(InlineKeyboardButton::callback(
+    buttons.push(callback_button(
         format!(
-            "{} Always show album art",
-            if user.cover_shown { "✅" } else { "⬜" }
+            "{} {}",
+            if user.broadcasts { "✅" } else { "⬜" },
+            tr!(lang, "pref-broadcasts")
         ),
+        from.id.0,
+        Payload::Preferences(if user.broadcasts {
+            PreferenceAction::BroadcastsOff
+        } else {
+            PreferenceAction::BroadcastsOn
+        }),
+    ));
+
+    buttons.push(callback_button(
         format!(
-            "{} preferences {}",
-            from.id,
-            if user.cover_shown {
-                "cover_hide"
-            } else {
-                "cover_show"
-            }
+            "{} {}",
+            if analytics { "✅" } else { "⬜" },
+            tr!(lang, "pref-analytics")
+        ),
+        from.id.0,
+        Payload::Preferences(if analytics {
+            PreferenceAction::AnalyticsOff
+        } else {
+            PreferenceAction::AnalyticsOn
+        }),
+    ));
+
+    buttons.push(callback_button(
+        format!(
+            "🗣 {}",
+            tr!(
+                lang,
+                "pref-personality",
+                personality = tr!(lang, &format!("personality-{personality}"))
+            )
         ),
+        from.id.0,
+        Payload::Preferences(match personality {
+            i18n::Personality::Uwu => PreferenceAction::PersonalityPlain,
+            i18n::Personality::Plain => PreferenceAction::PersonalityUwu,
+        }),
     ));
 
-    buttons.push(InlineKeyboardButton::callback(
-        "❌ Unlink your account",
-        format!("{} preferences {}", from.id, "unset"),
+    buttons.push(callback_button(
+        format!("❌ {}", tr!(lang, "pref-unlink")),
+        from.id.0,
+        Payload::Preferences(PreferenceAction::Unset),
     ));
 
     let buttons2d = buttons.into_iter().map(|x| vec![x]).collect::<Vec<_>>();
 
-    let name_text = utils::name_with_link(&from, &user);
+    let name_text = utils::name_with_link(from, &user);
     utils::send_or_edit_message(
         bot,
-        &format!("Settings for {name_text}"),
+        &tr!(
+            lang,
+            "settings",
+            name = name_text,
+            timezone = user.timezone(),
+            numbers = 1234567.to_formatted_string(&user.locale()),
+            locale = user.locale().name(),
+            language = i18n::language(lang),
+            languages = i18n::languages().join(", "),
+            personality = tr!(lang, &format!("personality-{personality}")),
+            personalities = personality_names()
+        ),
         msg,
         inline_message_id,
         edit,
@@ -856,20 +1546,67 @@ async fn preferences_command(
     Ok(())
 }
 
+// topkek artists 1m 25 p2 -> (entry type, period, as chart, count, page)
+// typed pages start at p1, the returned page starts at 0 like in Payload::Topkek
+fn parse_topkek_arg(arg: &str) -> (EntryType, TimePeriod, bool, usize, usize) {
+    let is_mode_word = |x: &str| x == "chart" || x == "image" || x == "img" || x == "text";
+    let as_page_word = |x: &str| {
+        x.strip_prefix('p')
+            .and_then(|p| p.parse::<usize>().ok())
+            .and_then(|p| p.checked_sub(1))
+    };
+
+    let words = arg.split_whitespace().collect::<Vec<_>>();
+    let as_chart = words
+        .iter()
+        .any(|x| *x == "chart" || *x == "image" || *x == "img");
+    let count = words
+        .iter()
+        .find_map(|x| x.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_TOP_COUNT);
+    let page = words
+        .iter()
+        .find_map(|x| as_page_word(x))
+        .unwrap_or_default();
+
+    // these would be parsed as a size or a period otherwise ("image" has an m)
+    let period_arg = words
+        .iter()
+        .filter(|x| !is_mode_word(x) && x.parse::<usize>().is_err() && as_page_word(x).is_none())
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let (_, period, entry_type, _) = utils::parse_collage_arg(&period_arg);
+
+    (entry_type, period, as_chart, count, page)
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn topkek_command(
     bot: &Bot,
     msg: Option<&Message>,
     inline_message_id: Option<String>,
     inline_from: Option<&teloxide::types::User>,
     edit: bool,
-    arg: &str,
+    entry_type: EntryType,
+    period: TimePeriod,
+    as_chart: bool,
+    count: usize,
+    page: usize,
     user: User,
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
-    let n = 5;
+    let locale = user.locale();
     let from = utils::choose_the_from(msg, inline_from);
+    let lang = i18n::lang(&from, Some(&user), msg.map(|x| x.chat.id));
+    let n = count.clamp(1, MAX_TOP_COUNT);
 
-    let (_, period, entry_type, _) = utils::parse_collage_arg(arg);
+    let msg_is_photo = msg
+        .as_ref()
+        .and_then(|m| m.photo())
+        .map(|_| true)
+        .unwrap_or(false);
 
+    // (html line, chart entry)
     let top_list = match entry_type {
         EntryType::Artist => {
             api_requester::fetch_artists(&user.account_username, &period, &user.api_type(), None)
@@ -877,17 +1614,27 @@ async fn topkek_command(
                 .map(|entries| {
                     entries
                         .iter()
-                        .take(n)
                         .map(|entry| {
                             let fragment = url_escape::encode_fragment(&entry.name);
                             let spotify_url =
                                 format!("https://open.spotify.com/search/{}", &fragment);
 
-                            format!(
-                                "<a href=\"{}\">{}</a> -> {} plays",
-                                spotify_url,
-                                utils::replace_html_symbols(&entry.name),
-                                entry.user_playcount.to_formatted_string(&Locale::en)
+                            (
+                                tr!(
+                                    lang,
+                                    "top-line",
+                                    entry = format!(
+                                        "<a href=\"{}\">{}</a>",
+                                        spotify_url,
+                                        utils::replace_html_symbols(&entry.name)
+                                    ),
+                                    plays = entry.user_playcount.to_formatted_string(&locale)
+                                ),
+                                ChartEntry {
+                                    title: entry.name.clone(),
+                                    playcount: entry.user_playcount,
+                                    image_url: None,
+                                },
                             )
                         })
                         .collect::<Vec<_>>()
@@ -899,18 +1646,28 @@ async fn topkek_command(
                 .map(|entries| {
                     entries
                         .iter()
-                        .take(n)
                         .map(|entry| {
                             let spotify_search_str = format!("{} {}", entry.name, entry.artist);
                             let fragment = url_escape::encode_fragment(spotify_search_str.as_str());
                             let spotify_url = format!("https://open.spotify.com/search/{fragment}");
 
-                            format!(
-                                "<a href=\"{}\">{} — {}</a> -> {} plays",
-                                spotify_url,
-                                utils::replace_html_symbols(&entry.artist),
-                                utils::replace_html_symbols(&entry.name),
-                                entry.user_playcount.to_formatted_string(&Locale::en)
+                            (
+                                tr!(
+                                    lang,
+                                    "top-line",
+                                    entry = format!(
+                                        "<a href=\"{}\">{} — {}</a>",
+                                        spotify_url,
+                                        utils::replace_html_symbols(&entry.artist),
+                                        utils::replace_html_symbols(&entry.name)
+                                    ),
+                                    plays = entry.user_playcount.to_formatted_string(&locale)
+                                ),
+                                ChartEntry {
+                                    title: format!("{} — {}", entry.artist, entry.name),
+                                    playcount: entry.user_playcount,
+                                    image_url: entry.album_art_url.clone(),
+                                },
                             )
                         })
                         .collect::<Vec<_>>()
@@ -922,64 +1679,160 @@ async fn topkek_command(
                 .map(|entries| {
                     entries
                         .iter()
-                        .take(n)
                         .map(|entry| {
                             let spotify_search_str = format!("{} {}", entry.name, entry.artist);
                             let fragment = url_escape::encode_fragment(spotify_search_str.as_str());
                             let spotify_url =
                                 format!("https://open.spotify.com/search/{}", &fragment);
 
-                            format!(
-                                "<a href=\"{}\">{} — {}</a> -> {} plays",
-                                spotify_url,
-                                utils::replace_html_symbols(&entry.artist),
-                                utils::replace_html_symbols(&entry.name),
-                                entry.user_playcount.to_formatted_string(&Locale::en)
+                            (
+                                tr!(
+                                    lang,
+                                    "top-line",
+                                    entry = format!(
+                                        "<a href=\"{}\">{} — {}</a>",
+                                        spotify_url,
+                                        utils::replace_html_symbols(&entry.artist),
+                                        utils::replace_html_symbols(&entry.name)
+                                    ),
+                                    plays = entry.user_playcount.to_formatted_string(&locale)
+                                ),
+                                ChartEntry {
+                                    title: format!("{} — {}", entry.artist, entry.name),
+                                    playcount: entry.user_playcount,
+                                    image_url: entry.album_art_url.clone(),
+                                },
                             )
                         })
                         .collect::<Vec<_>>()
                 })
         }
+    }?;
+
+    let total = top_list.len();
+    let last_page = total.saturating_sub(1) / n;
+    let page = page.min(last_page);
+    let first_rank = page * n + 1;
+
+    let (lines, chart_entries): (Vec<_>, Vec<_>) =
+        top_list.into_iter().skip(page * n).take(n).unzip();
+
+    let heading = tr!(
+        lang,
+        "top-heading",
+        name = utils::name_with_link(&from, &user),
+        entries = i18n::entries(lang, &entry_type),
+        period = i18n::period(lang, &period)
+    );
+
+    let cb_button = |text: &str, as_chart: bool, page: usize| {
+        callback_button(
+            text,
+            from.id.0,
+            Payload::Topkek {
+                entry_type: Some(entry_type),
+                period: Some(period),
+                as_chart,
+                count: n,
+                page,
+            },
+        )
     };
-    utils::send_or_edit_message(
-        bot,
-        &format!(
-            "{}'s top {}s for {}\n\n{}",
-            utils::name_with_link(&from, &user),
-            entry_type,
-            period,
-            top_list?
-                .iter()
-                .enumerate()
-                .map(|(i, x)| format!("{}. {}", i + 1, x))
-                .collect::<Vec<_>>()
-                .join("\n")
-        ),
-        msg,
-        inline_message_id,
-        edit,
-        None,
-        true,
-    )
-    .await?;
+
+    let mut buttons = vec![];
+
+    if page > 0 {
+        buttons.push(cb_button("◀️", as_chart, page - 1));
+    }
+
+    if as_chart {
+        buttons.push(cb_button("📝", false, page));
+    } else {
+        buttons.push(cb_button("📊", true, page));
+    }
+
+    if page < last_page {
+        buttons.push(cb_button("▶️", as_chart, page + 1));
+    }
+
+    let keyboard = InlineKeyboardMarkup::new(vec![buttons]);
+
+    let text = format!(
+        "{}\n\n{}",
+        heading,
+        lines
+            .iter()
+            .enumerate()
+            .map(|(i, x)| format!("{}. {}", first_rank + i, x))
+            .collect::<Vec<_>>()
+            .join("\n")
+    );
+
+    if as_chart {
+        let chart_heading = tr!(
+            lang,
+            "top-heading",
+            name = from.first_name,
+            entries = i18n::entries(lang, &entry_type),
+            period = i18n::period(lang, &period)
+        );
+        let chart = chart::create_chart(&chart_entries, first_rank, &chart_heading, &locale).await;
+        match chart {
+            Ok(chart) => {
+                utils::send_or_edit_photo(
+                    bot,
+                    InputMediaPhoto::new(InputFile::memory(chart))
+                        .caption(heading)
+                        .parse_mode(ParseMode::Html),
+                    msg,
+                    inline_message_id.as_ref(),
+                    edit,
+                    Some(keyboard),
+                    true,
+                )
+                .await?;
+            }
+            Err(e) => {
+                log::error!("chart generator failed {e}");
+                send_err_msg(bot, msg, inline_message_id, edit, lang, e.into()).await;
+            }
+        }
+    } else {
+        // media can't be removed from a message and the list may not fit in a caption,
+        // so a chart gets a new text message
+        utils::send_or_edit_message(
+            bot,
+            &text,
+            msg,
+            inline_message_id,
+            edit && !msg_is_photo,
+            Some(keyboard),
+            true,
+        )
+        .await?;
+    }
 
     Ok(())
 }
+#[allow(clippy::too_many_arguments)]
 async fn collage_command(
     bot: &Bot,
     msg: Option<&Message>,
     inline_message_id: Option<String>,
     inline_from: Option<&teloxide::types::User>,
     edit: bool,
-    arg: &str,
+    period: TimePeriod,
+    size: u32,
+    style: CollageStyle,
     user: User,
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
     let from = utils::choose_the_from(msg, inline_from);
+    let lang = i18n::lang(&from, Some(&user), msg.map(|x| x.chat.id));
 
     if user.api_type() == ApiType::Librefm {
         utils::send_or_edit_message(
             bot,
-            consts::COLLAGE_LIBREFM,
+            &tr!(lang, "collage-librefm"),
             msg,
             inline_message_id,
             edit,
@@ -990,60 +1843,151 @@ async fn collage_command(
         return Ok(());
     }
 
-    let (size, period, _, no_text) = utils::parse_collage_arg(arg);
+    let mut style = style;
+    // groups can force collages without any text on them
+    if msg.is_some_and(|x| {
+        DB.lock()
+            .unwrap()
+            .fetch_chat_settings(x.chat.id.0)
+            .clean_collages
+    }) {
+        style.captions = Captions::Hidden;
+        style.ranks = false;
+        style.header = false;
+    }
 
     let albums =
         api_requester::fetch_albums(&user.account_username, &period, &user.api_type(), None).await;
     match albums {
         Ok(albums) => {
-            let img = collage::create_collage(&albums, size, !no_text).await;
+            let period_str = period.to_string();
+            let header_text = tr!(
+                lang,
+                "collage-heading",
+                name = from.first_name,
+                period = i18n::period(lang, &period)
+            );
+            let started = Instant::now();
+            let img = collage::create_collage(&albums, size, &style, &header_text).await;
+            metrics::observe("collage_render_seconds", &[], started.elapsed());
             match img {
                 Ok(img) => {
-                    let period_str = period.to_string();
                     let period_str_cb_data = period_str.replace(' ', "_");
-                    let caption = format!(
-                        "{}'s {} album collage",
-                        utils::name_with_link(&from, &user),
-                        period_str,
+                    let caption = tr!(
+                        lang,
+                        "collage-heading",
+                        name = utils::name_with_link(&from, &user),
+                        period = i18n::period(lang, &period)
                     );
 
-                    let notext_str = if no_text { "clean" } else { "" };
-                    let notext_str_inverse = if no_text { "" } else { "clean" };
+                    // telegram doesn't take webp as a photo
+                    let as_document = style.as_file
+                        || style.output_format() == OutputFormat::Webp
+                        || img.len() > MAX_PHOTO_BYTES;
+                    if as_document {
+                        let file_name = format!(
+                            "collage_{}_{}.{}",
+                            size,
+                            period_str_cb_data,
+                            style.output_format().extension()
+                        );
+                        utils::send_or_edit_document(
+                            bot,
+                            InputMediaDocument::new(InputFile::memory(img).file_name(file_name))
+                                .caption(caption)
+                                .parse_mode(ParseMode::Html),
+                            msg,
+                            inline_message_id.as_ref(),
+                        )
+                        .await?;
+
+                        // a button press on the collage above swapped its keyboard for ⌛, put it back,
+                        // without 📄 once its file is sent
+                        if let Some(markup) = msg.filter(|_| edit).and_then(|x| x.reply_markup()) {
+                            let mut markup = markup.clone();
+                            if style.as_file {
+                                for row in &mut markup.inline_keyboard {
+                                    row.retain(|x| x.text != "📄");
+                                }
+                            }
+                            utils::edit_markup(bot, msg, None, markup).await?;
+                        }
+                        return Ok(());
+                    }
+
+                    let cb_data = |size: u32, style: CollageStyle| {
+                        Callback::new(
+                            from.id.0,
+                            Payload::Collage {
+                                period: Some(period),
+                                size: Some(size),
+                                style,
+                            },
+                        )
+                        .encode(&DB.lock().unwrap())
+                    };
+
+                    let toggled = |toggle: fn(&mut CollageStyle)| {
+                        let mut new_style = style;
+                        toggle(&mut new_style);
+                        cb_data(size, new_style)
+                    };
 
-                    let mut buttons = vec![vec![]];
+                    let mut buttons = vec![vec![], vec![]];
 
                     if size < collage::MAX_SIZE {
                         buttons[0].push(InlineKeyboardButton::callback(
                             "➕",
-                            format!(
-                                "{} collage {} {} {}",
-                                from.id,
-                                size + 1,
-                                period_str_cb_data,
-                                notext_str
-                            ),
+                            cb_data(size + 1, style),
                         ));
                     }
 
                     if size > collage::MIN_SIZE {
                         buttons[0].push(InlineKeyboardButton::callback(
                             "➖",
-                            format!(
-                                "{} collage {} {} {}",
-                                from.id,
-                                size - 1,
-                                period_str_cb_data,
-                                notext_str
-                            ),
+                            cb_data(size - 1, style),
                         ));
                     }
 
                     buttons[0].push(InlineKeyboardButton::callback(
                         "Aa",
-                        format!(
-                            "{} collage {} {} {}",
-                            from.id, size, period_str_cb_data, notext_str_inverse
-                        ),
+                        toggled(|s| s.captions = s.captions.next()),
+                    ));
+
+                    buttons[0].push(InlineKeyboardButton::callback(
+                        "🌈",
+                        toggled(|s| s.rainbow = !s.rainbow),
+                    ));
+
+                    buttons[1].push(InlineKeyboardButton::callback(
+                        "🔢",
+                        toggled(|s| s.ranks = !s.ranks),
+                    ));
+                    buttons[1].push(InlineKeyboardButton::callback(
+                        "🔳",
+                        toggled(|s| s.gaps = !s.gaps),
+                    ));
+                    buttons[1].push(InlineKeyboardButton::callback(
+                        "🔘",
+                        toggled(|s| s.rounded = !s.rounded),
+                    ));
+                    buttons[1].push(InlineKeyboardButton::callback(
+                        "🌓",
+                        toggled(|s| {
+                            s.theme = if s.theme == Theme::Dark {
+                                Theme::Light
+                            } else {
+                                Theme::Dark
+                            }
+                        }),
+                    ));
+                    buttons[1].push(InlineKeyboardButton::callback(
+                        "🔝",
+                        toggled(|s| s.header = !s.header),
+                    ));
+                    buttons[1].push(InlineKeyboardButton::callback(
+                        "📄",
+                        toggled(|s| s.as_file = true),
                     ));
 
                     let keyboard = InlineKeyboardMarkup::new(buttons);
@@ -1063,47 +2007,58 @@ async fn collage_command(
                 }
                 Err(e) => {
                     log::error!("collage generator failed {e}");
-                    send_err_msg(bot, msg, inline_message_id, edit, e.into()).await;
+                    send_err_msg(bot, msg, inline_message_id, edit, lang, e.into()).await;
                 }
             }
         }
         Err(e) => {
             log::error!("user.gettopalbums failed {e}");
-            send_err_msg(bot, msg, inline_message_id, edit, e).await;
+            send_err_msg(bot, msg, inline_message_id, edit, lang, e).await;
         }
     }
 
     Ok(())
 }
 
+// for handlers that don't have the registered user at hand
+fn lang_of(from: &teloxide::types::User, chat_id: Option<ChatId>) -> &'static str {
+    let db_user = DB.lock().unwrap().fetch_user(from.id.0);
+    i18n::lang(from, db_user.as_ref(), chat_id)
+}
+
 async fn type_chooser(
     bot: &Bot,
     msg: Option<&Message>,
     inline_message_id: Option<String>,
     inline_from: Option<&teloxide::types::User>,
     edit: bool,
-    command: &str,
+    payload: Payload,
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
     let from = utils::choose_the_from(msg, inline_from);
+    let lang = lang_of(&from, msg.map(|x| x.chat.id));
     let user_id = from.id.0;
+    let button = |icon: &str, entry_type: EntryType| {
+        callback_button(
+            format!("{icon} {}", tr!(lang, &format!("button-{entry_type}"))),
+            user_id,
+            payload.clone().with_entry_type(entry_type),
+        )
+    };
     let keyboard = InlineKeyboardMarkup::new(vec![vec![
-        InlineKeyboardButton::callback("🎵 Track", format!("{user_id} {command} track")),
-        InlineKeyboardButton::callback("💿 Album", format!("{user_id} {command} album")),
-        InlineKeyboardButton::callback("🎙️ Artist", format!("{user_id} {command} artist")),
+        button("🎵", EntryType::Track),
+        button("💿", EntryType::Album),
+        button("🎙️", EntryType::Artist),
     ]]);
 
     let direct_usage_text = if inline_message_id.is_none() {
-        format!(
-            "\n\n{}",
-            COMMAND_USAGE_MAP[command.split_whitespace().next().unwrap()]
-        )
+        format!("\n\n{}", tr!(lang, COMMAND_USAGE_MAP[payload.name()]))
     } else {
         "".to_string()
     };
 
     utils::send_or_edit_message(
         bot,
-        &format!("Choose type:{direct_usage_text}"),
+        &format!("{}{direct_usage_text}", tr!(lang, "choose-type")),
         msg,
         inline_message_id,
         edit,
@@ -1120,35 +2075,36 @@ async fn period_chooser(
     inline_message_id: Option<String>,
     inline_from: Option<&teloxide::types::User>,
     edit: bool,
-    commands: &str,
+    payload: Payload,
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
     let from = utils::choose_the_from(msg, inline_from);
+    let lang = lang_of(&from, msg.map(|x| x.chat.id));
     let user_id = from.id.0;
+    let button = |text: &str, period: TimePeriod| {
+        callback_button(text, user_id, payload.clone().with_period(period))
+    };
     let keyboard = InlineKeyboardMarkup::new(vec![
         vec![
-            InlineKeyboardButton::callback("1w", format!("{user_id} {commands} 1w")),
-            InlineKeyboardButton::callback("1m", format!("{user_id} {commands} 1m")),
-            InlineKeyboardButton::callback("3m", format!("{user_id} {commands} 3m")),
+            button("1w", TimePeriod::OneWeek),
+            button("1m", TimePeriod::OneMonth),
+            button("3m", TimePeriod::ThreeMonths),
         ],
         vec![
-            InlineKeyboardButton::callback("6m", format!("{user_id} {commands} 6m")),
-            InlineKeyboardButton::callback("1y", format!("{user_id} {commands} 1y")),
-            InlineKeyboardButton::callback("alltime", format!("{user_id} {commands} alltime")),
+            button("6m", TimePeriod::SixMonths),
+            button("1y", TimePeriod::OneYear),
+            button(&tr!(lang, "button-alltime"), TimePeriod::AllTime),
         ],
     ]);
 
     let direct_usage_text = if inline_message_id.is_none() {
-        format!(
-            "\n\n{}",
-            COMMAND_USAGE_MAP[commands.split_whitespace().next().unwrap()]
-        )
+        format!("\n\n{}", tr!(lang, COMMAND_USAGE_MAP[payload.name()]))
     } else {
         "".to_string()
     };
 
     utils::send_or_edit_message(
         bot,
-        &format!("Choose time period:{direct_usage_text}"),
+        &format!("{}{direct_usage_text}", tr!(lang, "choose-period")),
         msg,
         inline_message_id,
         edit,
@@ -1165,35 +2121,27 @@ async fn size_chooser(
     inline_message_id: Option<String>,
     inline_from: Option<&teloxide::types::User>,
     edit: bool,
-    commands: &str,
+    payload: Payload,
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
     let from = utils::choose_the_from(msg, inline_from);
+    let lang = lang_of(&from, msg.map(|x| x.chat.id));
     let user_id = from.id.0;
+    let button =
+        |size: u32| callback_button(size.to_string(), user_id, payload.clone().with_size(size));
     let keyboard = InlineKeyboardMarkup::new(vec![
-        vec![
-            InlineKeyboardButton::callback("1", format!("{user_id} {commands} 1")),
-            InlineKeyboardButton::callback("2", format!("{user_id} {commands} 2")),
-            InlineKeyboardButton::callback("3", format!("{user_id} {commands} 3")),
-        ],
-        vec![
-            InlineKeyboardButton::callback("4", format!("{user_id} {commands} 4")),
-            InlineKeyboardButton::callback("5", format!("{user_id} {commands} 5")),
-            InlineKeyboardButton::callback("6", format!("{user_id} {commands} 6")),
-        ],
+        vec![button(1), button(2), button(3)],
+        vec![button(4), button(5), button(6)],
     ]);
 
     let direct_usage_text = if inline_message_id.is_none() {
-        format!(
-            "\n\n{}",
-            COMMAND_USAGE_MAP[commands.split_whitespace().next().unwrap()]
-        )
+        format!("\n\n{}", tr!(lang, COMMAND_USAGE_MAP[payload.name()]))
     } else {
         "".to_string()
     };
 
     utils::send_or_edit_message(
         bot,
-        &format!("Choose a size:{direct_usage_text}"),
+        &format!("{}{direct_usage_text}", tr!(lang, "choose-size")),
         msg,
         inline_message_id,
         edit,
@@ -1204,16 +2152,20 @@ async fn size_chooser(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn random_command(
     bot: &Bot,
     msg: Option<&Message>,
     inline_message_id: Option<String>,
     inline_from: Option<&teloxide::types::User>,
     edit: bool,
-    args: &str,
+    entry_type: EntryType,
+    period: TimePeriod,
     user: User,
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let locale = user.locale();
     let from = utils::choose_the_from(msg, inline_from);
+    let lang = i18n::lang(&from, Some(&user), msg.map(|x| x.chat.id));
 
     let username = user.account_username.to_owned();
     let api_type = user.api_type();
@@ -1222,7 +2174,6 @@ async fn random_command(
     } else {
         1000
     };
-    let (_, period, entry_type, _) = utils::parse_collage_arg(args);
 
     let text: Option<String>;
     let mut search_text: Option<String> = None;
@@ -1233,10 +2184,11 @@ async fn random_command(
                 api_requester::fetch_artists(&username, &period, &api_type, limit.into()).await?;
             text = arr.choose(&mut rand::rng()).map(|x| {
                 search_text = x.name.clone().into();
-                format!(
-                    "{}\n({} plays)",
-                    utils::replace_html_symbols(&x.name),
-                    x.user_playcount.to_formatted_string(&Locale::en)
+                tr!(
+                    lang,
+                    "random-entry",
+                    entry = utils::replace_html_symbols(&x.name),
+                    plays = x.user_playcount.to_formatted_string(&locale)
                 )
             });
         }
@@ -1246,11 +2198,15 @@ async fn random_command(
             text = arr.choose(&mut rand::rng()).map(|x| {
                 search_text = (x.artist.clone() + " " + &x.name.clone()).into();
                 album_art_url = x.album_art_url.clone();
-                format!(
-                    "{} — {}\n({} plays)",
-                    utils::replace_html_symbols(&x.artist),
-                    utils::replace_html_symbols(&x.name),
-                    x.user_playcount.to_formatted_string(&Locale::en)
+                tr!(
+                    lang,
+                    "random-entry",
+                    entry = format!(
+                        "{} — {}",
+                        utils::replace_html_symbols(&x.artist),
+                        utils::replace_html_symbols(&x.name)
+                    ),
+                    plays = x.user_playcount.to_formatted_string(&locale)
                 )
             });
         }
@@ -1274,11 +2230,15 @@ async fn random_command(
                     }
                 }
 
-                text = Some(format!(
-                    "{} — {}\n({} plays)",
-                    utils::replace_html_symbols(&track.artist),
-                    utils::replace_html_symbols(&track.name),
-                    track.user_playcount.to_formatted_string(&Locale::en)
+                text = Some(tr!(
+                    lang,
+                    "random-entry",
+                    entry = format!(
+                        "{} — {}",
+                        utils::replace_html_symbols(&track.artist),
+                        utils::replace_html_symbols(&track.name)
+                    ),
+                    plays = track.user_playcount.to_formatted_string(&locale)
                 ));
             } else {
                 text = None;
@@ -1295,15 +2255,25 @@ async fn random_command(
 
             let keyboard = InlineKeyboardMarkup::new(vec![vec![
                 InlineKeyboardButton::url("🔎", spotify_url),
-                InlineKeyboardButton::callback("🔃", format!("{} random {}", from.id.0, args)),
+                callback_button(
+                    "🔃",
+                    from.id.0,
+                    Payload::Random {
+                        entry_type: Some(entry_type),
+                        period: Some(period),
+                    },
+                ),
             ]]);
 
             let text = format!(
-                "{}'s random {} for {}\n\n{}",
-                utils::name_with_link(&from, &user),
-                entry_type,
-                period,
-                text,
+                "{}\n\n{text}",
+                tr!(
+                    lang,
+                    "random-heading",
+                    name = utils::name_with_link(&from, &user),
+                    entry = i18n::entry(lang, &entry_type),
+                    period = i18n::period(lang, &period)
+                )
             );
 
             if entry_type == EntryType::Artist {
@@ -1340,7 +2310,7 @@ async fn random_command(
         None => {
             utils::send_or_edit_message(
                 bot,
-                consts::NOT_FOUND,
+                &tr!(lang, "not-found"),
                 msg,
                 inline_message_id,
                 edit,
@@ -1362,7 +2332,9 @@ async fn flex_command(
     edit: bool,
     user: User,
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let locale = user.locale();
     let from = utils::choose_the_from(msg, inline_from);
+    let lang = i18n::lang(&from, Some(&user), msg.map(|x| x.chat.id));
 
     let scrobble_user =
         api_requester::fetch_user_info(&user.account_username, &user.api_type()).await?;
@@ -1374,16 +2346,71 @@ async fn flex_command(
 
     let scrobbling_since = scrobble_user
         .registered_date
-        .map(|x| "\n\nSince ".to_owned() + &utils::format_epoch_secs(x, false))
+        .map(|x| {
+            let date = utils::format_epoch_secs(x, false, &user.timezone());
+            format!("\n\n{}", tr!(lang, "flex-since", date = date))
+        })
+        .unwrap_or_default();
+
+    let streak =
+        milestones::current_streak(&user.account_username, &user.api_type(), &user.timezone())
+            .await
+            .unwrap_or_else(|e| {
+                log::warn!("streak failed {e}");
+                0
+            });
+    let streak_text = if streak > 0 {
+        format!(
+            "\n\n🔥 {}",
+            tr!(
+                lang,
+                "flex-streak",
+                days = milestones::format_streak(streak)
+            )
+        )
+    } else {
+        String::new()
+    };
+
+    let fmt = |x: u64| x.to_formatted_string(&locale);
+    let reached = [
+        milestones::reached(scrobble_user.playcount, milestones::SCROBBLE_MILESTONES)
+            .map(|x| tr!(lang, "count-plays", count = fmt(x))),
+        milestones::reached(scrobble_user.artist_count, milestones::ARTIST_MILESTONES)
+            .map(|x| tr!(lang, "count-artists", count = fmt(x))),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+    let reached_text = if reached.is_empty() {
+        String::new()
+    } else {
+        format!("\n🏅 {}", reached.join(", "))
+    };
+    let next_text = milestones::next(scrobble_user.playcount, milestones::SCROBBLE_MILESTONES)
+        .map(|x| {
+            let left = fmt(x - scrobble_user.playcount);
+            format!(
+                "\n🎯 {}",
+                tr!(lang, "flex-next", plays = left, milestone = fmt(x))
+            )
+        })
         .unwrap_or_default();
 
     let text = format!(
-        "{}\n\n{} artists\n{} albums\n{} tracks\n{} plays{}",
+        "{}\n\n{}{}{}{}{}",
         utils::name_with_link(&from, &user),
-        scrobble_user.artist_count.to_formatted_string(&Locale::en),
-        scrobble_user.album_count.to_formatted_string(&Locale::en),
-        scrobble_user.track_count.to_formatted_string(&Locale::en),
-        scrobble_user.playcount.to_formatted_string(&Locale::en),
+        tr!(
+            lang,
+            "flex-counts",
+            artists = fmt(scrobble_user.artist_count),
+            albums = fmt(scrobble_user.album_count),
+            tracks = fmt(scrobble_user.track_count),
+            plays = fmt(scrobble_user.playcount)
+        ),
+        streak_text,
+        reached_text,
+        next_text,
         scrobbling_since,
     );
 
@@ -1404,19 +2431,56 @@ async fn flex_command(
     Ok(())
 }
 
-async fn compat_command(
+async fn clock_command(
     bot: &Bot,
     msg: &Message,
     arg: &str,
-    db_user1_u: User,
+    user: User,
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
-    let user1 = msg.from.as_ref().unwrap();
-    let reply_to_msg = msg.reply_to_message();
+    let locale = user.locale();
+    let from = msg.from.as_ref().unwrap();
+    let lang = i18n::lang(from, Some(&user), Some(msg.chat.id));
+    let period = if arg.trim().is_empty() {
+        TimePeriod::OneMonth
+    } else {
+        utils::parse_collage_arg(arg).1
+    };
+
+    let to_ts = Utc::now().timestamp() as u64;
+    let from_ts = api_requester::time_period_secs(&period)
+        .map(|x| to_ts.saturating_sub(x))
+        .unwrap_or_default();
+
+    bot.send_chat_action(msg.chat.id, ChatAction::UploadPhoto)
+        .await?;
+
+    let (scrobbles, complete) = api_requester::fetch_scrobbles_between(
+        &user.account_username,
+        &user.api_type(),
+        from_ts,
+        to_ts,
+        CLOCK_MAX_PAGES,
+    )
+    .await?;
 
-    if reply_to_msg.is_none() || reply_to_msg.unwrap().from.as_ref().is_none() {
+    let timezone = user.timezone();
+    let mut hours = [0u64; 24];
+    let mut grid = [[0u64; 24]; 7];
+    for date in scrobbles.iter().filter_map(|x| x.date) {
+        let Some(time) = DateTime::from_timestamp(date as i64, 0) else {
+            continue;
+        };
+        let time = time.with_timezone(&timezone);
+        let hour = time.hour() as usize;
+        hours[hour] += 1;
+        grid[time.weekday().num_days_from_monday() as usize][hour] += 1;
+    }
+
+    let total: u64 = hours.iter().sum();
+    if total == 0 {
         utils::send_or_edit_message(
             bot,
-            consts::COMPAT_CLICK,
+            &tr!(lang, "no-scrobbles"),
             msg.into(),
             None,
             false,
@@ -1424,81 +2488,1664 @@ async fn compat_command(
             true,
         )
         .await?;
-
         return Ok(());
     }
 
-    let user2 = reply_to_msg.unwrap().from.as_ref().unwrap();
-    let db_user2 = DB.lock().unwrap().fetch_user(user2.id.0);
-
-    let text: String = if user1.id.0 == user2.id.0 {
-        consts::ITS_ME.to_string()
-    } else if user1.is_bot || user2.is_bot {
-        consts::BOTS_MUSIC.to_string()
-    } else if let Some(db_user2) = db_user2 {
-        let (_size, period, _, _no_text) = utils::parse_collage_arg(arg);
-        let period_text = period.to_string();
-
-        let username1 = db_user1_u.account_username.clone();
-        let username2 = db_user2.account_username.clone();
-        let api_type1 = db_user1_u.api_type();
-        let api_type2 = db_user2.api_type();
-
-        let artists1 =
-            api_requester::fetch_artists(&username1, &TimePeriod::OneYear, &api_type1, None)
-                .await?;
-        let artists2 =
-            api_requester::fetch_artists(&username2, &TimePeriod::OneYear, &api_type2, None)
-                .await?;
-
-        let mut numerator = 0;
-        let mut mutual: Vec<String> = Vec::new();
-        let denominator = min(min(artists1.len(), artists2.len()), 40);
-
-        for artist1 in &artists1 {
-            for artist2 in &artists2 {
-                if artist1.name == artist2.name {
-                    numerator += 1;
-                    if mutual.len() < 8 {
-                        mutual.push(artist1.name.clone());
-                    }
-                    break;
-                }
-            }
-        }
+    let (busiest_hour, busiest_count) = hours
+        .iter()
+        .enumerate()
+        .max_by_key(|x| (x.1, std::cmp::Reverse(x.0)))
+        .unwrap();
+    let busiest_day = (0..7)
+        .max_by_key(|&day| (grid[day].iter().sum::<u64>(), std::cmp::Reverse(day)))
+        .map(|day| i18n::weekday(lang, day))
+        .unwrap_or_default();
 
-        log::info!("common artists = {numerator}/{denominator}");
+    let heading = |name: &str| {
+        tr!(
+            lang,
+            "clock-heading",
+            name = name,
+            period = i18n::period(lang, &period)
+        )
+    };
+    let caption = format!(
+        "{}\n\n{}",
+        heading(&utils::name_with_link(from, &user)),
+        tr!(
+            lang,
+            if complete {
+                "clock-summary"
+            } else {
+                "clock-summary-partial"
+            },
+            scrobbles = total.to_formatted_string(&locale),
+            hour = format!("{busiest_hour:02}:00"),
+            hour_scrobbles = busiest_count.to_formatted_string(&locale),
+            day = busiest_day,
+            timezone = timezone
+        )
+    );
 
-        let mut score = 0;
-        if denominator > 2 {
-            score = numerator * 100 / denominator;
+    let weekdays = std::array::from_fn(|day| i18n::weekday_short(lang, day));
+    match chart::create_listening_clock(&hours, &grid, &weekdays, &heading(&from.first_name)) {
+        Ok(img) => {
+            utils::send_or_edit_photo(
+                bot,
+                InputMediaPhoto::new(InputFile::memory(img))
+                    .caption(caption)
+                    .parse_mode(ParseMode::Html),
+                msg.into(),
+                None,
+                false,
+                None,
+                true,
+            )
+            .await?;
         }
-        if score > 100 {
-            score = 100;
+        Err(e) => {
+            log::error!("listening clock generator failed {e}");
+            send_err_msg(bot, msg.into(), None, false, lang, e.into()).await;
         }
+    }
+
+    Ok(())
+}
+
+async fn wrapped_command(
+    bot: &Bot,
+    msg: &Message,
+    arg: &str,
+    user: User,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let locale = user.locale();
+    let from = msg.from.as_ref().unwrap();
+    let lang = i18n::lang(from, Some(&user), Some(msg.chat.id));
+    let current_year = Utc::now().year();
+    let year = match arg.trim() {
+        "" => current_year,
+        x => match x.parse::<i32>() {
+            Ok(year) if (2002..=current_year).contains(&year) => year,
+            _ => {
+                utils::send_or_edit_message(
+                    bot,
+                    &tr!(lang, "wrapped-usage"),
+                    msg.into(),
+                    None,
+                    false,
+                    None,
+                    true,
+                )
+                .await?;
+                return Ok(());
+            }
+        },
+    };
+
+    let timezone = user.timezone();
+    let year_start = |year: i32| {
+        NaiveDate::from_ymd_opt(year, 1, 1)
+            .and_then(|x| x.and_hms_opt(0, 0, 0))
+            .and_then(|x| x.and_local_timezone(timezone).earliest())
+            .map(|x| x.timestamp() as u64)
+            .unwrap_or_default()
+    };
+    let from_ts = year_start(year);
+    let to_ts = year_start(year + 1) - 1;
+
+    bot.send_chat_action(msg.chat.id, ChatAction::UploadPhoto)
+        .await?;
+
+    let (scrobbles, complete) = api_requester::fetch_scrobbles_between(
+        &user.account_username,
+        &user.api_type(),
+        from_ts,
+        to_ts,
+        WRAPPED_MAX_PAGES,
+    )
+    .await?;
+
+    if scrobbles.is_empty() {
+        utils::send_or_edit_message(
+            bot,
+            &tr!(lang, "no-scrobbles"),
+            msg.into(),
+            None,
+            false,
+            None,
+            true,
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let artists_before = api_requester::fetch_artists_between(
+        &user.account_username,
+        &user.api_type(),
+        0,
+        from_ts - 1,
+    )
+    .await
+    .ok()
+    .map(|x| x.into_iter().map(|x| x.name.to_lowercase()).collect());
+
+    let report = wrapped::build(year, &scrobbles, artists_before, &timezone);
+
+    let list = |entries: &[(String, usize)]| {
+        entries
+            .iter()
+            .enumerate()
+            .map(|(i, (name, count))| {
+                format!(
+                    "{}. {} ({})",
+                    i + 1,
+                    utils::replace_html_symbols(name),
+                    count.to_formatted_string(&locale)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let mut sections = vec![format!(
+        "{}\n\n{}",
+        tr!(
+            lang,
+            "wrapped-heading",
+            name = utils::name_with_link(from, &user),
+            year = year
+        ),
+        tr!(
+            lang,
+            if complete {
+                "wrapped-total"
+            } else {
+                "wrapped-total-partial"
+            },
+            scrobbles = report.total.to_formatted_string(&locale)
+        )
+    )];
+    let mut section = |icon: &str, id: &str, entries: &[(String, usize)]| {
+        sections.push(format!("{icon} {}\n{}", tr!(lang, id), list(entries)));
+    };
+    section("🎙️", "wrapped-top-artists", &report.top_artists);
+    section("💿", "wrapped-top-albums", &report.top_albums);
+    section("🎵", "wrapped-top-tracks", &report.top_tracks);
+    if let Some(new_artists) = &report.new_artists {
+        sections.push(format!(
+            "✨ {}\n{}",
+            tr!(
+                lang,
+                "wrapped-new-artists-like",
+                count = report.new_artists_count.to_formatted_string(&locale)
+            ),
+            list(new_artists)
+        ));
+    }
+
+    let scrobbles = |count: usize| {
+        tr!(
+            lang,
+            "count-scrobbles",
+            count = count.to_formatted_string(&locale)
+        )
+    };
+    let mut facts = vec![];
+    if let Some((month, count)) = report.busiest_month {
+        facts.push(tr!(
+            lang,
+            "wrapped-busiest-month",
+            month = i18n::month(lang, month),
+            scrobbles = scrobbles(count)
+        ));
+    }
+    if let Some((day, count)) = report.busiest_day {
+        facts.push(tr!(
+            lang,
+            "wrapped-busiest-day",
+            day = day.format("%Y-%m-%d"),
+            scrobbles = scrobbles(count)
+        ));
+    }
+    if let Some((start, end, len)) = report.longest_streak {
+        facts.push(tr!(
+            lang,
+            "wrapped-longest-streak-range",
+            days = len,
+            start = start.format("%Y-%m-%d"),
+            end = end.format("%Y-%m-%d")
+        ));
+    }
+    if !facts.is_empty() {
+        sections.push(format!("📅 {}", facts.join("\n")));
+    }
+
+    let caption = sections[0].clone();
+    let details = sections[1..].join("\n\n");
+
+    match wrapped::create_summary_image(&report, &from.first_name, &locale, lang).await {
+        Ok(img) => {
+            utils::send_or_edit_photo(
+                bot,
+                InputMediaPhoto::new(InputFile::memory(img))
+                    .caption(caption)
+                    .parse_mode(ParseMode::Html),
+                msg.into(),
+                None,
+                false,
+                None,
+                true,
+            )
+            .await?;
+            utils::send_or_edit_message(bot, &details, msg.into(), None, false, None, true).await?;
+        }
+        Err(e) => {
+            log::error!("wrapped image generator failed {e}");
+            utils::send_or_edit_message(
+                bot,
+                &format!("{caption}\n\n{details}"),
+                msg.into(),
+                None,
+                false,
+                None,
+                true,
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+fn is_genre_tag(tag: &str) -> bool {
+    tag.split(' ').any(|x| ACCEPTABLE_TAGS.contains(x))
+}
+
+async fn genres_command(
+    bot: &Bot,
+    msg: Option<&Message>,
+    inline_message_id: Option<String>,
+    inline_from: Option<&teloxide::types::User>,
+    edit: bool,
+    arg: &str,
+    user: User,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let from = utils::choose_the_from(msg, inline_from);
+    let lang = i18n::lang(&from, Some(&user), msg.map(|x| x.chat.id));
+
+    let is_chart_word = |x: &str| x == "chart" || x == "pie" || x == "image" || x == "img";
+    let as_chart = arg.split_whitespace().any(is_chart_word);
+    let period_arg = arg
+        .split_whitespace()
+        .filter(|x| !is_chart_word(x))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let (_, period, _, _) = utils::parse_collage_arg(&period_arg);
+
+    let artists = api_requester::fetch_artists(
+        &user.account_username,
+        &period,
+        &user.api_type(),
+        Some(GENRES_ARTIST_COUNT),
+    )
+    .await?;
+
+    // each artist's plays are split between its genre tags
+    let mut weights: HashMap<String, f64> = HashMap::new();
+    for chunk in artists.chunks(10) {
+        let reqs = chunk
+            .iter()
+            .map(|artist| {
+                task::spawn(api_requester::fetch_lastfm_artist(
+                    None,
+                    artist.name.clone(),
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        for (artist, req) in chunk.iter().zip(reqs) {
+            let genres = req
+                .await?
+                .ok()
+                .and_then(|x| x.tags)
+                .unwrap_or_default()
+                .iter()
+                .map(|t| t.to_lowercase())
+                .filter(|t| is_genre_tag(t))
+                .take(3)
+                .collect::<Vec<_>>();
+
+            for genre in &genres {
+                *weights.entry(genre.clone()).or_default() +=
+                    artist.user_playcount as f64 / genres.len() as f64;
+            }
+        }
+    }
+
+    let total: f64 = weights.values().sum();
+    if total <= 0.0 {
+        utils::send_or_edit_message(
+            bot,
+            &tr!(lang, "no-genres"),
+            msg,
+            inline_message_id,
+            edit,
+            None,
+            true,
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let mut genres = weights
+        .into_iter()
+        .map(|(genre, weight)| (genre, weight / total))
+        .collect::<Vec<_>>();
+    genres.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let lines = genres
+        .iter()
+        .take(GENRES_SHOWN)
+        .enumerate()
+        .map(|(i, (genre, share))| {
+            format!(
+                "{}. {} — {:.1}%",
+                i + 1,
+                utils::replace_html_symbols(genre),
+                share * 100.0
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let heading = |name: &str| {
+        tr!(
+            lang,
+            "genres-heading",
+            name = name,
+            period = i18n::period(lang, &period)
+        )
+    };
+    let text = format!(
+        "{}\n\n{}",
+        heading(&utils::name_with_link(&from, &user)),
+        lines.join("\n")
+    );
+
+    if !as_chart {
+        utils::send_or_edit_message(bot, &text, msg, inline_message_id, edit, None, true).await?;
+        return Ok(());
+    }
+
+    let mut slices = genres
+        .iter()
+        .take(GENRES_SHOWN - 1)
+        .cloned()
+        .collect::<Vec<_>>();
+    let other: f64 = genres.iter().skip(GENRES_SHOWN - 1).map(|x| x.1).sum();
+    if other > 0.0 {
+        slices.push((tr!(lang, "genres-other"), other));
+    }
+
+    match chart::create_pie_chart(&slices, &heading(&from.first_name)) {
+        Ok(img) => {
+            utils::send_or_edit_photo(
+                bot,
+                InputMediaPhoto::new(InputFile::memory(img))
+                    .caption(text)
+                    .parse_mode(ParseMode::Html),
+                msg,
+                inline_message_id.as_ref(),
+                edit,
+                None,
+                true,
+            )
+            .await?;
+        }
+        Err(e) => {
+            log::error!("pie chart generator failed {e}");
+            send_err_msg(bot, msg, inline_message_id, edit, lang, e.into()).await;
+        }
+    }
+
+    Ok(())
+}
+
+// None entry_type shows the user's plays of the track, album and artist together
+#[allow(clippy::too_many_arguments)]
+async fn entity_command(
+    bot: &Bot,
+    msg: Option<&Message>,
+    inline_message_id: Option<String>,
+    inline_from: Option<&teloxide::types::User>,
+    edit: bool,
+    entry_type: Option<EntryType>,
+    arg: &str,
+    user: User,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let locale = user.locale();
+    let from = utils::choose_the_from(msg, inline_from);
+    let lang = i18n::lang(&from, Some(&user), msg.map(|x| x.chat.id));
+    let is_lastfm = user.api_type() == ApiType::Lastfm;
+    // user play counts only make sense for lastfm accounts
+    let lastfm_username = is_lastfm.then(|| user.account_username.clone());
+
+    let (artist, album, title) = if arg.trim().is_empty() {
+        let tracks =
+            api_requester::fetch_recent_tracks(&user.account_username, &user.api_type(), true, 1)
+                .await?;
+
+        match tracks.into_iter().next() {
+            Some(track) => (track.artist, track.album, Some(track.name)),
+            None => {
+                utils::send_or_edit_message(
+                    bot,
+                    &tr!(lang, "no-scrobbles"),
+                    msg,
+                    inline_message_id,
+                    edit,
+                    None,
+                    true,
+                )
+                .await?;
+                return Ok(());
+            }
+        }
+    } else if entry_type == Some(EntryType::Artist) {
+        (arg.trim().to_string(), None, None)
+    } else {
+        let (artist, title) = utils::split_artist_title(arg);
+        if entry_type == Some(EntryType::Album) {
+            (artist, title, None)
+        } else {
+            (artist, None, title)
+        }
+    };
+
+    let missing_arg = match entry_type {
+        Some(EntryType::Artist) => artist.is_empty(),
+        Some(EntryType::Album) => album.is_none(),
+        Some(EntryType::Track) | None => title.is_none(),
+    };
+
+    if missing_arg {
+        utils::send_or_edit_message(
+            bot,
+            &tr!(lang, "entity-usage"),
+            msg,
+            inline_message_id,
+            edit,
+            None,
+            true,
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let total_scrobbles = if is_lastfm {
+        api_requester::fetch_user_info(&user.account_username, &user.api_type())
+            .await
+            .map(|x| x.playcount)
+            .unwrap_or_default()
+    } else {
+        0
+    };
+
+    let plays_text = |plays: u64| {
+        if !is_lastfm {
+            return String::new();
+        }
+
+        let plays_str = plays.to_formatted_string(&locale);
+        let text = if total_scrobbles > 0 {
+            tr!(
+                lang,
+                "entity-plays-share",
+                plays = plays_str,
+                share = format!("{:.2}", plays as f64 * 100.0 / total_scrobbles as f64)
+            )
+        } else {
+            tr!(lang, "count-plays", count = plays_str)
+        };
+
+        format!("\n{text}")
+    };
+
+    let global_text = |listeners: u64, playcount: u64| {
+        format!(
+            "\n{}",
+            tr!(
+                lang,
+                "entity-global",
+                listeners = listeners.to_formatted_string(&locale),
+                scrobbles = playcount.to_formatted_string(&locale)
+            )
+        )
+    };
+
+    let tags_text = |tags: Option<Vec<String>>| {
+        let hashtags = utils::hashtags(
+            tags.unwrap_or_default()
+                .iter()
+                .take(5)
+                .map(|t| t.to_lowercase()),
+        );
+        if hashtags.is_empty() {
+            hashtags
+        } else {
+            format!("\n\n{hashtags}")
+        }
+    };
+
+    let dates_text = |dates: Result<(Option<u64>, Option<u64>), Box<dyn Error + Send + Sync>>| {
+        dates
+            .ok()
+            .and_then(|(first, last)| Some((first?, last?)))
+            .map(|(first, last)| {
+                format!(
+                    "\n{}",
+                    tr!(
+                        lang,
+                        "entity-scrobble-dates",
+                        first = utils::format_epoch_secs(first, false, &user.timezone()),
+                        last = utils::format_epoch_secs(last, false, &user.timezone())
+                    )
+                )
+            })
+            .unwrap_or_default()
+    };
+
+    let mut album_art_url = None;
+
+    let text = match entry_type {
+        Some(EntryType::Artist) => {
+            let dates_text = if is_lastfm {
+                dates_text(
+                    api_requester::fetch_lastfm_artist_scrobble_dates(
+                        &user.account_username,
+                        &artist,
+                    )
+                    .await,
+                )
+            } else {
+                "".to_string()
+            };
+
+            let e = api_requester::fetch_lastfm_artist(lastfm_username, artist).await?;
+            format!(
+                "🎙️ <b>{}</b>{}{}{}{}",
+                utils::replace_html_symbols(&e.name),
+                plays_text(e.user_playcount),
+                global_text(e.listeners, e.playcount),
+                dates_text,
+                tags_text(e.tags),
+            )
+        }
+        Some(EntryType::Album) => {
+            let e = api_requester::fetch_lastfm_album(
+                lastfm_username.as_deref().unwrap_or_default(),
+                &artist,
+                album.as_deref().unwrap_or_default(),
+            )
+            .await?;
+            album_art_url = e.album_art_url.clone();
+
+            let dates_text = if is_lastfm {
+                dates_text(
+                    api_requester::fetch_lastfm_album_scrobble_dates(
+                        &user.account_username,
+                        &e.artist,
+                        &e.name,
+                    )
+                    .await,
+                )
+            } else {
+                "".to_string()
+            };
+
+            format!(
+                "💿 <b>{}</b> — <i>{}</i>{}{}{}{}",
+                utils::replace_html_symbols(&e.name),
+                utils::replace_html_symbols(&e.artist),
+                plays_text(e.user_playcount),
+                global_text(e.listeners, e.playcount),
+                dates_text,
+                tags_text(e.tags),
+            )
+        }
+        Some(EntryType::Track) => {
+            let title = title.unwrap_or_default();
+            let dates_text = if is_lastfm {
+                dates_text(
+                    api_requester::fetch_lastfm_track_scrobble_dates(
+                        &user.account_username,
+                        &artist,
+                        &title,
+                    )
+                    .await,
+                )
+            } else {
+                "".to_string()
+            };
+
+            let e = api_requester::fetch_lastfm_track(lastfm_username, artist, title).await?;
+            format!(
+                "🎵 <b>{}</b> — <i>{}</i> ({}){}{}{}{}{}",
+                utils::replace_html_symbols(&e.name),
+                utils::replace_html_symbols(&e.artist),
+                if e.duration > 0 {
+                    utils::human_readable_duration(e.duration)
+                } else {
+                    "??:??".to_string()
+                },
+                e.album
+                    .as_ref()
+                    .map(|x| format!("\n💿 {}", utils::replace_html_symbols(x)))
+                    .unwrap_or_default(),
+                plays_text(e.user_playcount),
+                global_text(e.listeners, e.playcount),
+                dates_text,
+                tags_text(e.tags),
+            )
+        }
+        None => {
+            let title = title.unwrap_or_default();
+            let track_req = task::spawn(api_requester::fetch_lastfm_track(
+                lastfm_username.clone(),
+                artist.clone(),
+                title,
+            ));
+            let artist_req = task::spawn(api_requester::fetch_lastfm_artist(
+                lastfm_username.clone(),
+                artist.clone(),
+            ));
+            let album_info = match &album {
+                Some(album) => api_requester::fetch_lastfm_album(
+                    lastfm_username.as_deref().unwrap_or_default(),
+                    &artist,
+                    album,
+                )
+                .await
+                .ok(),
+                None => None,
+            };
+
+            let mut lines = vec![];
+            if let Ok(e) = track_req.await? {
+                lines.push(format!(
+                    "🎵 <b>{}</b>{}",
+                    utils::replace_html_symbols(&e.name),
+                    plays_text(e.user_playcount)
+                ));
+            }
+            if let Some(e) = album_info {
+                lines.push(format!(
+                    "💿 <b>{}</b>{}",
+                    utils::replace_html_symbols(&e.name),
+                    plays_text(e.user_playcount)
+                ));
+            }
+            if let Ok(e) = artist_req.await? {
+                lines.push(format!(
+                    "🎙️ <b>{}</b>{}",
+                    utils::replace_html_symbols(&e.name),
+                    plays_text(e.user_playcount)
+                ));
+            }
+
+            if lines.is_empty() {
+                tr!(lang, "not-found")
+            } else {
+                format!(
+                    "{}\n\n{}",
+                    tr!(
+                        lang,
+                        "entity-plays-heading",
+                        name = utils::name_with_link(&from, &user)
+                    ),
+                    lines.join("\n\n")
+                )
+            }
+        }
+    };
+
+    if let Some(album_art_url) = album_art_url {
+        utils::send_or_edit_photo(
+            bot,
+            InputMediaPhoto::new(InputFile::url(Url::parse(&album_art_url)?))
+                .caption(text)
+                .parse_mode(ParseMode::Html),
+            msg,
+            inline_message_id.as_ref(),
+            edit,
+            None,
+            false,
+        )
+        .await?;
+    } else {
+        utils::send_or_edit_message(bot, &text, msg, inline_message_id, edit, None, true).await?;
+    }
+
+    Ok(())
+}
+
+async fn milestones_command(bot: &Bot, msg: &Message) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let from = msg.from.as_ref().unwrap();
+    let lang = lang_of(from, Some(msg.chat.id));
+    if msg.chat.is_private() {
+        utils::send_or_edit_message(
+            bot,
+            &tr!(lang, "milestones-private"),
+            msg.into(),
+            None,
+            false,
+            None,
+            true,
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let member = bot.get_chat_member(msg.chat.id, from.id).await?;
+    if !member.is_privileged() {
+        utils::send_or_edit_message(
+            bot,
+            &tr!(lang, "admins-only"),
+            msg.into(),
+            None,
+            false,
+            None,
+            true,
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let enabled = {
+        let db = DB.lock().unwrap();
+        let enabled = !db.is_milestone_chat(msg.chat.id.0);
+        db.set_milestone_chat(msg.chat.id.0, enabled)?;
+        enabled
+    };
+
+    let text = if enabled {
+        tr!(lang, "milestones-on")
+    } else {
+        tr!(lang, "milestones-off")
+    };
+    utils::send_or_edit_message(bot, &text, msg.into(), None, false, None, true).await?;
+
+    Ok(())
+}
+
+fn personality_names() -> String {
+    i18n::PERSONALITIES
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// group admins pick the voice for everyone in the chat
+async fn personality_command(
+    bot: &Bot,
+    msg: &Message,
+    arg: &str,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let from = msg.from.as_ref().unwrap();
+    let lang = lang_of(from, Some(msg.chat.id));
+    let reply = |text: String| async move {
+        utils::send_or_edit_message(bot, &text, msg.into(), None, false, None, true).await
+    };
+
+    if msg.chat.is_private() {
+        reply(tr!(
+            lang,
+            "personality-private",
+            personalities = personality_names()
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    let personality = match arg.trim().to_lowercase().as_str() {
+        "default" | "auto" => None,
+        x => match x.parse::<i18n::Personality>() {
+            Ok(personality) => Some(personality),
+            Err(_) => {
+                let current = DB.lock().unwrap().fetch_chat_personality(msg.chat.id.0);
+                let current = match current {
+                    Some(x) => tr!(lang, &format!("personality-{x}")),
+                    None => tr!(lang, "personality-default"),
+                };
+                reply(tr!(
+                    lang,
+                    "personality-chat-usage",
+                    personality = current,
+                    personalities = personality_names()
+                ))
+                .await?;
+                return Ok(());
+            }
+        },
+    };
+
+    let member = bot.get_chat_member(msg.chat.id, from.id).await?;
+    if !member.is_privileged() {
+        reply(tr!(lang, "admins-only")).await?;
+        return Ok(());
+    }
+
+    DB.lock()
+        .unwrap()
+        .set_chat_personality(msg.chat.id.0, personality)?;
+
+    // the command list shown in this chat follows the voice too
+    let lang = lang_of(from, Some(msg.chat.id));
+    let scope = BotCommandScope::Chat {
+        chat_id: Recipient::Id(msg.chat.id),
+    };
+    let commands_result = match personality {
+        Some(_) => bot
+            .set_my_commands(bot_commands(lang))
+            .scope(scope)
+            .await
+            .map(|_| ()),
+        None => bot.delete_my_commands().scope(scope).await.map(|_| ()),
+    };
+    if let Err(e) = commands_result {
+        log::warn!("chat commands for {} failed {e}", msg.chat.id);
+    }
+
+    let text = match personality {
+        Some(x) => tr!(
+            lang,
+            "personality-chat-set",
+            personality = tr!(lang, &format!("personality-{x}"))
+        ),
+        None => tr!(lang, "personality-chat-default"),
+    };
+    reply(text).await?;
+
+    Ok(())
+}
+
+// edit is set for button presses, the callback handler checks for admins then
+// /chatsettings toggle collage, clean on, period 1m, ...
+fn parse_chatsettings_arg(arg: &str) -> Option<ChatSettingsAction> {
+    let words = arg.to_lowercase();
+    let words = words.split_whitespace().collect::<Vec<_>>();
+    match words.as_slice() {
+        ["toggle", command] => Some(ChatSettingsAction::ToggleCommand(command.to_string())),
+        ["enable", command] => Some(ChatSettingsAction::EnableCommand(command.to_string())),
+        ["disable", command] => Some(ChatSettingsAction::DisableCommand(command.to_string())),
+        ["clean", x @ ("on" | "off")] => Some(ChatSettingsAction::Clean(*x == "on")),
+        ["slashless", x @ ("on" | "off")] => Some(ChatSettingsAction::Slashless(*x == "on")),
+        ["period", "default" | "auto"] => Some(ChatSettingsAction::Period(None)),
+        ["period", x] => (0..)
+            .map_while(TimePeriod::from_repr)
+            .find(|period| callback::period_arg(*period) == *x)
+            .map(|period| ChatSettingsAction::Period(Some(period))),
+        ["topic"] => Some(ChatSettingsAction::ToggleTopic),
+        _ => None,
+    }
+}
+
+async fn chatsettings_command(
+    bot: &Bot,
+    msg: &Message,
+    from: &teloxide::types::User,
+    action: Option<ChatSettingsAction>,
+    edit: bool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let lang = lang_of(from, Some(msg.chat.id));
+
+    if msg.chat.is_private() {
+        utils::send_or_edit_message(
+            bot,
+            &tr!(lang, "group-only"),
+            msg.into(),
+            None,
+            false,
+            None,
+            true,
+        )
+        .await?;
+        return Ok(());
+    }
+
+    if action.is_some()
+        && !edit
+        && !bot
+            .get_chat_member(msg.chat.id, from.id)
+            .await?
+            .is_privileged()
+    {
+        utils::send_or_edit_message(
+            bot,
+            &tr!(lang, "admins-only"),
+            msg.into(),
+            None,
+            false,
+            None,
+            true,
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let mut settings = DB.lock().unwrap().fetch_chat_settings(msg.chat.id.0);
+    let toggleable = |x: &str| VISIBLE_COMMANDS.contains(&x) && !LOCKED_COMMANDS.contains(&x);
+    let changed = match action {
+        Some(ChatSettingsAction::ToggleCommand(command)) if toggleable(&command) => {
+            settings.toggle_command(&command);
+            true
+        }
+        Some(ChatSettingsAction::DisableCommand(command))
+            if toggleable(&command) && !settings.is_disabled(&command) =>
+        {
+            settings.toggle_command(&command);
+            true
+        }
+        Some(ChatSettingsAction::EnableCommand(command)) if settings.is_disabled(&command) => {
+            settings.toggle_command(&command);
+            true
+        }
+        Some(ChatSettingsAction::Clean(on)) => {
+            settings.clean_collages = on;
+            true
+        }
+        Some(ChatSettingsAction::Slashless(on)) => {
+            settings.slashless = on;
+            true
+        }
+        Some(ChatSettingsAction::Period(period)) => {
+            settings.set_default_period(period);
+            true
+        }
+        Some(ChatSettingsAction::ToggleTopic) => {
+            settings.toggle_topic(topic_id(msg));
+            true
+        }
+        _ => false,
+    };
+    if changed {
+        DB.lock().unwrap().upsert_chat_settings(&settings)?;
+    }
+
+    let on_off = |x: bool| {
+        tr!(
+            lang,
+            if x {
+                "chatsettings-on"
+            } else {
+                "chatsettings-off"
+            }
+        )
+    };
+    let check = |x: bool| if x { "✅" } else { "⬜" };
+    let button = |text: String, action: ChatSettingsAction| {
+        callback_button(text, 0, Payload::ChatSettings(action))
+    };
+    let period_text = match settings.default_period() {
+        Some(period) => i18n::period(lang, &period),
+        None => tr!(lang, "chatsettings-period-none"),
+    };
+
+    let mut buttons = vec![vec![
+        button(
+            format!(
+                "{} {}",
+                check(settings.slashless),
+                tr!(lang, "chatsettings-slashless")
+            ),
+            ChatSettingsAction::Slashless(!settings.slashless),
+        ),
+        button(
+            format!(
+                "{} {}",
+                check(settings.clean_collages),
+                tr!(lang, "chatsettings-clean")
+            ),
+            ChatSettingsAction::Clean(!settings.clean_collages),
+        ),
+    ]];
+
+    // cycles through the periods, then back to asking every time
+    let next_period = match settings.default_period() {
+        None => Some(TimePeriod::OneWeek),
+        Some(period) => TimePeriod::from_repr(period as u8 + 1),
+    };
+    buttons.push(vec![button(
+        format!(
+            "📅 {}",
+            tr!(lang, "chatsettings-period", period = period_text.clone())
+        ),
+        ChatSettingsAction::Period(next_period),
+    )]);
+
+    if msg.is_topic_message {
+        buttons.push(vec![button(
+            format!(
+                "{} {}",
+                check(settings.is_allowed_topic(topic_id(msg))),
+                tr!(lang, "chatsettings-topic")
+            ),
+            ChatSettingsAction::ToggleTopic,
+        )]);
+    }
+
+    let command_buttons = VISIBLE_COMMANDS
+        .iter()
+        .filter(|x| !LOCKED_COMMANDS.contains(x))
+        .map(|x| {
+            button(
+                format!(
+                    "{} {x}",
+                    if settings.is_disabled(x) {
+                        "🚫"
+                    } else {
+                        "✅"
+                    }
+                ),
+                ChatSettingsAction::ToggleCommand(x.to_string()),
+            )
+        })
+        .collect::<Vec<_>>();
+    buttons.extend(command_buttons.chunks(3).map(|x| x.to_vec()));
+
+    let disabled = settings.disabled_commands();
+    let text = tr!(
+        lang,
+        "chatsettings",
+        slashless = on_off(settings.slashless),
+        clean = on_off(settings.clean_collages),
+        period = period_text,
+        topics = match settings.allowed_topics_count() {
+            0 => tr!(lang, "chatsettings-topics-all"),
+            count => tr!(lang, "chatsettings-topics-some", count = count),
+        },
+        disabled = if disabled.is_empty() {
+            tr!(lang, "chatsettings-none")
+        } else {
+            disabled.join(", ")
+        }
+    );
+
+    utils::send_or_edit_message(
+        bot,
+        &text,
+        msg.into(),
+        None,
+        edit,
+        InlineKeyboardMarkup::new(buttons).into(),
+        true,
+    )
+    .await?;
+
+    Ok(())
+}
+
+fn is_owner(user: &teloxide::types::User) -> bool {
+    config::OWNER_ID.parse::<u64>() == Ok(user.id.0)
+}
+
+async fn owner_command(
+    bot: &Bot,
+    msg: &Message,
+    command: Command,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let lang = lang_of(msg.from.as_ref().unwrap(), Some(msg.chat.id));
+    let text = match command {
+        Command::Stats { arg } => {
+            // so the counts include the last minute
+            anal::flush().await;
+            stats_text(lang, arg.trim())
+        }
+        Command::Broadcast { arg } if arg.trim().is_empty() => tr!(lang, "owner-broadcast-usage"),
+        Command::Broadcast { arg } => {
+            broadcast(bot, msg, lang, arg.trim()).await?;
+            return Ok(());
+        }
+        Command::Lookup { arg } => lookup_text(lang, arg.trim()),
+        Command::ReloadLocales => match i18n::reload() {
+            Ok(count) => {
+                set_commands(bot).await?;
+                tr!(lang, "owner-reloaded", count = count)
+            }
+            Err(e) => tr!(lang, "owner-reload-failed", error = e),
+        },
+        Command::Cache { arg } => {
+            let cache = &api_requester::HTTP_CACHE;
+            if arg.trim() == "flush" {
+                cache.invalidate_all();
+            }
+            cache.run_pending_tasks().await;
+            tr!(
+                lang,
+                "owner-cache",
+                entries = cache.entry_count(),
+                capacity = cache.policy().max_capacity().unwrap_or_default(),
+                ttl = cache.policy().time_to_live().unwrap_or_default().as_secs()
+            )
+        }
+        Command::Upstreams => upstreams_text(lang, health::check_all(bot).await),
+        _ => return Ok(()),
+    };
+
+    utils::send_or_edit_message(bot, &text, msg.into(), None, false, None, true).await?;
+    Ok(())
+}
+
+fn upstreams_text(lang: &str, probes: BTreeMap<String, health::Probe>) -> String {
+    let mut lines = vec![];
+    for (name, probe) in probes {
+        lines.push(tr!(
+            lang,
+            "owner-upstream",
+            icon = if probe.ok { "✅" } else { "❌" },
+            name = name,
+            latency = probe.latency_ms
+        ));
+        if let (Some(error), Some(at)) = (probe.last_error, probe.last_error_at) {
+            lines.push(tr!(
+                lang,
+                "owner-upstream-error",
+                error = utils::replace_html_symbols(&error),
+                ago = utils::convert_to_timeago(at)
+            ));
+        }
+        if let Some(open_for) = name
+            .parse::<ApiType>()
+            .ok()
+            .and_then(health::breaker_open_for)
+        {
+            lines.push(tr!(
+                lang,
+                "owner-upstream-breaker",
+                seconds = open_for.as_secs()
+            ));
+        }
+    }
+    lines.join("\n")
+}
+
+// /stats 7 counts events of the last 7 days, just today's by default
+fn stats_text(lang: &str, arg: &str) -> String {
+    let now = Utc::now().timestamp() as u64;
+    let day = 60 * 60 * 24;
+    let days = arg.parse::<u64>().unwrap_or(1).max(1);
+    let since = now.saturating_sub(days * day);
+    let db = DB.lock().unwrap();
+    let api_types = db.count_users_by_api_type();
+    let active = [1, 7, 30].map(|days| db.count_active_users(now.saturating_sub(days * day)));
+    let events = db.count_events(since * 1000);
+    let daily = db.count_events_by_day(since * 1000);
+    drop(db);
+
+    tr!(
+        lang,
+        "owner-stats",
+        users = api_types.iter().map(|x| x.1).sum::<u64>(),
+        api_types = api_types
+            .iter()
+            .map(|(api_type, count)| format!("{api_type}: {count}"))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        day = active[0],
+        week = active[1],
+        month = active[2],
+        since = utils::format_epoch_secs(since, true, &chrono_tz::Tz::UTC),
+        commands = events
+            .iter()
+            .map(|(event, count)| format!("{event}: {count}"))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        daily = daily
+            .iter()
+            .map(|(date, events, users)| {
+                tr!(
+                    lang,
+                    "owner-stats-day",
+                    date = date,
+                    events = events,
+                    users = users
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    )
+}
+
+// a telegram id, or a @username the bot has seen
+fn lookup_text(lang: &str, arg: &str) -> String {
+    if arg.is_empty() {
+        return tr!(lang, "owner-lookup-usage");
+    }
+    let query = arg.trim_start_matches('@');
+    let db = DB.lock().unwrap();
+    let user = match query.parse::<u64>() {
+        Ok(tg_user_id) => db.fetch_user(tg_user_id),
+        Err(_) => db.fetch_user_by_tg_username(query),
+    };
+    let Some(user) = user else {
+        return tr!(
+            lang,
+            "owner-lookup-none",
+            query = utils::replace_html_symbols(arg)
+        );
+    };
+    let last_seen = db.fetch_last_seen(user.tg_user_id);
+    drop(db);
+
+    let on_off = |x: bool| {
+        tr!(
+            lang,
+            if x {
+                "chatsettings-on"
+            } else {
+                "chatsettings-off"
+            }
+        )
+    };
+    tr!(
+        lang,
+        "owner-lookup",
+        id = user.tg_user_id,
+        account = utils::account_name_with_link(&user.account_username, &user),
+        service = user.api_type(),
+        profile = on_off(user.profile_shown),
+        cover = on_off(user.cover_shown),
+        milestones = on_off(user.notify_milestones),
+        broadcasts = on_off(user.broadcasts),
+        timezone = user.timezone(),
+        locale = user.locale().name(),
+        language = user.language().unwrap_or("auto"),
+        personality = match user.personality() {
+            Some(x) => tr!(lang, &format!("personality-{x}")),
+            None => tr!(lang, "personality-default"),
+        },
+        last_seen = match last_seen {
+            Some(x) => utils::format_epoch_secs(x, true, &chrono_tz::Tz::UTC),
+            None => tr!(lang, "owner-never"),
+        }
+    )
+}
+
+// one message per user who didn't opt out, with a note on how to
+async fn broadcast(
+    bot: &Bot,
+    msg: &Message,
+    lang: &str,
+    text: &str,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let users = DB.lock().unwrap().fetch_broadcast_users();
+    let total = users.len();
+    let progress = bot
+        .send_message(
+            msg.chat.id,
+            tr!(
+                lang,
+                "owner-broadcast-progress",
+                sent = 0,
+                failed = 0,
+                total = total
+            ),
+        )
+        .await?;
+
+    let mut sent = 0;
+    let mut failed = 0;
+    for (i, user) in users.iter().enumerate() {
+        let language = user
+            .language()
+            .and_then(i18n::supported)
+            .unwrap_or(i18n::DEFAULT_LANG);
+        let user_lang = i18n::voice(language, i18n::personality(Some(user), None));
+        let result = bot
+            .send_message(
+                UserId(user.tg_user_id),
+                format!("{text}\n\n{}", tr!(user_lang, "broadcast-footer")),
+            )
+            .parse_mode(ParseMode::Html)
+            .await;
+        match result {
+            Ok(_) => sent += 1,
+            Err(e) => {
+                failed += 1;
+                log::warn!("broadcast to {} failed {e}", user.tg_user_id);
+            }
+        }
+
+        if (i + 1) % BROADCAST_PROGRESS_EVERY == 0 {
+            let _ = bot
+                .edit_message_text(
+                    msg.chat.id,
+                    progress.id,
+                    tr!(
+                        lang,
+                        "owner-broadcast-progress",
+                        sent = sent,
+                        failed = failed,
+                        total = total
+                    ),
+                )
+                .await;
+        }
+    }
+
+    bot.edit_message_text(
+        msg.chat.id,
+        progress.id,
+        tr!(
+            lang,
+            "owner-broadcast-done",
+            sent = sent,
+            failed = failed,
+            total = total
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+async fn tastematrix_command(
+    bot: &Bot,
+    msg: &Message,
+    arg: &str,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let lang = lang_of(msg.from.as_ref().unwrap(), Some(msg.chat.id));
+    if msg.chat.is_private() {
+        utils::send_or_edit_message(
+            bot,
+            &tr!(lang, "group-only"),
+            msg.into(),
+            None,
+            false,
+            None,
+            true,
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let (_, period, _, _) = utils::parse_collage_arg(arg);
+
+    let known_members = DB
+        .lock()
+        .unwrap()
+        .fetch_chat_members(msg.chat.id.0, TASTEMATRIX_MAX_MEMBERS);
+
+    // drop people who left since they last used the bot
+    let mut members = vec![];
+    for db_user in known_members {
+        match bot
+            .get_chat_member(msg.chat.id, UserId(db_user.tg_user_id))
+            .await
+        {
+            Ok(member) if member.is_present() => members.push((member.user, db_user)),
+            Ok(_) => {
+                let _ = DB
+                    .lock()
+                    .unwrap()
+                    .delete_chat_member(msg.chat.id.0, db_user.tg_user_id);
+            }
+            Err(e) => log::warn!("get_chat_member failed {e}"),
+        }
+    }
+
+    if members.len() < 2 {
+        utils::send_or_edit_message(
+            bot,
+            &tr!(lang, "tastematrix-few-members"),
+            msg.into(),
+            None,
+            false,
+            None,
+            true,
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let mut profiles = vec![];
+    for (_, db_user) in &members {
+        profiles.push(
+            compat::fetch_profile(&db_user.account_username, &db_user.api_type(), &period).await,
+        );
+    }
+
+    let mut scores = vec![vec![None; members.len()]; members.len()];
+    for i in 0..members.len() {
+        for j in (i + 1)..members.len() {
+            let score = compat::compare(&profiles[i], &profiles[j]).map(|x| x.score);
+            scores[i][j] = score;
+            scores[j][i] = score;
+        }
+    }
+
+    let lines = members
+        .iter()
+        .enumerate()
+        .map(|(i, (tg_user, db_user))| {
+            let peers = scores[i]
+                .iter()
+                .enumerate()
+                .filter_map(|(j, score)| Some((j, (*score)?)))
+                .filter(|(j, _)| *j != i)
+                .collect::<Vec<_>>();
+            let most = peers.iter().max_by_key(|x| x.1);
+            let least = peers.iter().min_by_key(|x| x.1);
+
+            let peer_text = |peer: Option<&(usize, u32)>| {
+                peer.map(|(j, score)| {
+                    format!(
+                        "{} ({}%)",
+                        utils::replace_html_symbols(&members[*j].0.first_name),
+                        score
+                    )
+                })
+                .unwrap_or("?".to_string())
+            };
 
-        if mutual.is_empty() || score == 0 {
-            format!("No common artists in {period_text}")
-        } else {
             format!(
-                "{} and {} listen to {}\n\nCompatibility score is {}%, based on {}",
-                utils::name_with_link(user1, &db_user1_u),
-                utils::name_with_link(user2, &db_user2),
-                mutual
-                    .iter()
-                    .map(|x| utils::replace_html_symbols(x))
-                    .collect::<Vec<_>>()
-                    .join(", ")
-                    + "...",
-                score,
-                period_text,
+                "{}. {}",
+                i + 1,
+                tr!(
+                    lang,
+                    "tastematrix-line",
+                    name = utils::name_with_link(tg_user, db_user),
+                    most = peer_text(most),
+                    least = peer_text(least)
+                )
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let heading = tr!(
+        lang,
+        "tastematrix-heading",
+        period = i18n::period(lang, &period)
+    );
+    let text = format!("{heading}\n\n{}", lines.join("\n"));
+
+    let names = members
+        .iter()
+        .map(|(tg_user, _)| tg_user.first_name.clone())
+        .collect::<Vec<_>>();
+
+    match chart::create_heatmap(&names, &scores, &heading) {
+        Ok(img) => {
+            utils::send_or_edit_photo(
+                bot,
+                InputMediaPhoto::new(InputFile::memory(img))
+                    .caption(text)
+                    .parse_mode(ParseMode::Html),
+                msg.into(),
+                None,
+                false,
+                None,
+                true,
+            )
+            .await?;
+        }
+        Err(e) => {
+            log::error!("heatmap generator failed {e}");
+            utils::send_or_edit_message(bot, &text, msg.into(), None, false, None, true).await?;
+        }
+    }
+
+    Ok(())
+}
+
+// the other side of a compat, resolved from a reply, a mention or a service:username word,
+// errors are message ids
+fn resolve_compat_target(
+    msg: Option<&Message>,
+    from: &teloxide::types::User,
+    target_word: Option<&str>,
+) -> Result<(String, User), &'static str> {
+    if let Some(word) = target_word {
+        if let Some(tg_username) = word.strip_prefix('@') {
+            if from
+                .username
+                .as_ref()
+                .is_some_and(|x| x.eq_ignore_ascii_case(tg_username))
+            {
+                return Err("its-me");
+            }
+
+            // hidden profiles are only found from a group both are in, and look unregistered otherwise
+            let db = DB.lock().unwrap();
+            return match db.fetch_user_by_tg_username(tg_username) {
+                Some(db_user)
+                    if db_user.profile_shown
+                        || msg.is_some_and(|m| {
+                            !m.chat.is_private()
+                                && db.is_chat_member(m.chat.id.0, db_user.tg_user_id)
+                        }) =>
+                {
+                    Ok((utils::account_name_with_link(word, &db_user), db_user))
+                }
+                _ => Err("mention-not-registered"),
+            };
+        }
+
+        if let Some((api_type, username)) = word.split_once(':') {
+            let api_type = api_type.parse().map_err(|_| "compat-click")?;
+            let profile = User::new(0, username.to_string(), &api_type, true, false);
+            return Ok((utils::account_name_with_link(username, &profile), profile));
+        }
+    }
+
+    // text mentions are how users without a username get mentioned
+    let mentioned = msg
+        .and_then(|m| m.entities())
+        .unwrap_or_default()
+        .iter()
+        .find_map(|e| match &e.kind {
+            MessageEntityKind::TextMention { user } => Some(user.clone()),
+            _ => None,
+        });
+    let replied = msg
+        .and_then(|m| m.reply_to_message())
+        .and_then(|m| m.from.clone());
+
+    let tg_user = mentioned.or(replied).ok_or("compat-click")?;
+
+    if tg_user.id == from.id {
+        return Err("its-me");
+    }
+    if tg_user.is_bot {
+        return Err("bots-music");
+    }
+
+    let db_user = DB
+        .lock()
+        .unwrap()
+        .fetch_user(tg_user.id.0)
+        .ok_or("they-not-registered")?;
+
+    Ok((utils::name_with_link(&tg_user, &db_user), db_user))
+}
+
+async fn compat_command(
+    bot: &Bot,
+    msg: Option<&Message>,
+    inline_message_id: Option<String>,
+    inline_from: Option<&teloxide::types::User>,
+    edit: bool,
+    arg: &str,
+    db_user1_u: User,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let user1 = utils::choose_the_from(msg, inline_from);
+    let lang = i18n::lang(&user1, Some(&db_user1_u), msg.map(|x| x.chat.id));
+
+    let is_target_word = |x: &str| {
+        x.starts_with('@')
+            || x.split_once(':')
+                .is_some_and(|(api_type, _)| api_type.parse::<ApiType>().is_ok())
+    };
+    let target_word = arg.split_whitespace().find(|x| is_target_word(x));
+    let period_arg = arg
+        .split_whitespace()
+        .filter(|x| !is_target_word(x))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let text = match resolve_compat_target(msg, &user1, target_word) {
+        Err(id) => tr!(lang, id),
+        Ok((user2_name, db_user2)) => {
+            let (_, period, _, _) = utils::parse_collage_arg(&period_arg);
+            let period_text = i18n::period(lang, &period);
+
+            let compatibility = compat::compute(
+                (&db_user1_u.account_username, &db_user1_u.api_type()),
+                (&db_user2.account_username, &db_user2.api_type()),
+                &period,
             )
+            .await?;
+
+            log::info!(
+                "compat score = {}, shared artists = {}",
+                compatibility.score,
+                compatibility.shared_artists.len()
+            );
+
+            if compatibility.shared_artists.is_empty() || compatibility.score == 0 {
+                tr!(lang, "compat-nothing-common", period = period_text)
+            } else {
+                let percent = |x: Option<f64>| {
+                    x.map(|x| format!("{:.0}%", x * 100.0))
+                        .unwrap_or("—".to_string())
+                };
+
+                tr!(
+                    lang,
+                    "compat-result",
+                    name1 = utils::name_with_link(&user1, &db_user1_u),
+                    name2 = user2_name,
+                    artists = compatibility
+                        .shared_artists
+                        .iter()
+                        .take(COMPAT_ARTISTS_SHOWN)
+                        .map(|(name, share)| format!(
+                            "{} ({:.0}%)",
+                            utils::replace_html_symbols(name),
+                            share * 100.0
+                        ))
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                    score = compatibility.score,
+                    period = period_text,
+                    artists_score = percent(compatibility.artists),
+                    albums_score = percent(compatibility.albums),
+                    tracks_score = percent(compatibility.tracks)
+                )
+            }
         }
-    } else {
-        consts::THEY_NOT_REGISTERED.to_string()
     };
 
-    utils::send_or_edit_message(bot, text.as_str(), msg.into(), None, false, None, true).await?;
+    utils::send_or_edit_message(bot, &text, msg, inline_message_id, edit, None, true).await?;
     Ok(())
 }
 
@@ -1508,75 +4155,73 @@ async fn inline_query_handler(
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
     let user = DB.lock().unwrap().fetch_user(q.from.id.0);
 
-    let keyboard = InlineKeyboardMarkup::new(vec![vec![InlineKeyboardButton::callback(
-        "🎹",
-        "0 loading",
-    )]]);
+    let keyboard =
+        InlineKeyboardMarkup::new(vec![vec![callback_button("🎹", 0, Payload::Loading)]]);
+
+    let lang = i18n::lang(&q.from, user.as_ref(), None);
+    // the message text is only shown until the result is chosen and loaded
+    let article = |id: &str| {
+        InlineQueryResult::Article(
+            InlineQueryResultArticle::new(
+                id,
+                tr!(lang, &format!("inline-{id}")),
+                InputMessageContent::Text(InputMessageContentText::new(tr!(
+                    lang,
+                    &format!("inline-{id}-text")
+                ))),
+            )
+            .reply_markup(keyboard.clone()),
+        )
+    };
 
-    let status = InlineQueryResultArticle::new(
+    let mut results = [
         "status",
-        "Status",
-        InputMessageContent::Text(InputMessageContentText::new("Staaaaaaatus")),
-    )
-    .reply_markup(keyboard.clone());
-
-    let status_full = InlineQueryResultArticle::new(
         "status_full",
-        "Expanded Status",
-        InputMessageContent::Text(InputMessageContentText::new("Expanded Staaaaatus")),
-    )
-    .reply_markup(keyboard.clone());
-
-    let loved = InlineQueryResultArticle::new(
         "loved",
-        "Loved",
-        InputMessageContent::Text(InputMessageContentText::new("Loved")),
-    )
-    .reply_markup(keyboard.clone());
-
-    let random = InlineQueryResultArticle::new(
-        "random",
-        "Shuffle your scrobbles",
-        InputMessageContent::Text(InputMessageContentText::new("Shuffle your scrobbles")),
-    )
-    .reply_markup(keyboard.clone());
-
-    let flex = InlineQueryResultArticle::new(
         "flex",
-        "Flex your numbers",
-        InputMessageContent::Text(InputMessageContentText::new("Flex your numbers")),
-    )
-    .reply_markup(keyboard.clone());
-
-    let topkek = InlineQueryResultArticle::new(
         "topkek",
-        "Top artists/albums/tracks as text",
-        InputMessageContent::Text(InputMessageContentText::new(
-            "Top artists/albums/tracks as text",
-        )),
-    )
-    .reply_markup(keyboard.clone());
-
-    let collage = InlineQueryResultArticle::new(
+        "random",
         "collage",
-        "Album art collage",
-        InputMessageContent::Text(InputMessageContentText::new("Album art collage")),
-    )
-    .reply_markup(keyboard.clone());
-
-    let results = vec![
-        InlineQueryResult::Article(status),
-        InlineQueryResult::Article(status_full),
-        InlineQueryResult::Article(loved),
-        InlineQueryResult::Article(flex),
-        InlineQueryResult::Article(topkek),
-        InlineQueryResult::Article(random),
-        InlineQueryResult::Article(collage),
-    ];
+    ]
+    .into_iter()
+    .map(article)
+    .collect::<Vec<_>>();
+
+    // "compat @someone 1y" compares with a chosen member, the result id carries the argument
+    let compat_arg = q
+        .query
+        .trim()
+        .strip_prefix("compat")
+        .map(|x| x.trim().to_lowercase())
+        .filter(|x| !x.is_empty());
+    if let Some(compat_arg) = compat_arg {
+        // result ids are limited to 64 bytes
+        let mut result_id = format!("compat {compat_arg}");
+        while result_id.len() > 64 {
+            result_id.pop();
+        }
+        let compat = InlineQueryResultArticle::new(
+            result_id,
+            tr!(lang, "inline-compat", target = compat_arg),
+            InputMessageContent::Text(InputMessageContentText::new(tr!(
+                lang,
+                "inline-compat-text"
+            ))),
+        )
+        .reply_markup(keyboard.clone());
+        results.insert(0, InlineQueryResult::Article(compat));
+    }
+
+    if let Some(user) = &user {
+        let _ = DB
+            .lock()
+            .unwrap()
+            .update_tg_username(user.tg_user_id, q.from.username.as_deref());
+    }
 
     if user.is_none() {
         let switch_pm_button = InlineQueryResultsButton {
-            text: consts::NOT_REGISTERED_INLINE.to_string(),
+            text: tr!(lang, "not-registered-inline"),
             kind: InlineQueryResultsButtonKind::StartParameter("set".to_string()),
         };
 
@@ -1669,7 +4314,7 @@ async fn inline_result_handler(
                 chosen_inline_result.inline_message_id,
                 from,
                 true,
-                "collage",
+                Payload::collage(),
             )
             .await?;
             track("inline_collage", from).await;
@@ -1681,7 +4326,7 @@ async fn inline_result_handler(
                 chosen_inline_result.inline_message_id,
                 from,
                 true,
-                "random",
+                Payload::random(),
             )
             .await?;
             track("inline_random", from).await;
@@ -1705,11 +4350,24 @@ async fn inline_result_handler(
                 chosen_inline_result.inline_message_id,
                 from,
                 true,
-                "topkek",
+                Payload::topkek(DEFAULT_TOP_COUNT),
             )
             .await?;
             track("inline_topkek", from).await;
         }
+        "compat" => {
+            compat_command(
+                &bot,
+                None,
+                chosen_inline_result.inline_message_id,
+                from,
+                true,
+                splits.get(1).copied().unwrap_or_default(),
+                user,
+            )
+            .await?;
+            track("inline_compat", from).await;
+        }
         _ => {
             log::error!("Unknown result id: {result_id}");
         }
@@ -1722,6 +4380,8 @@ async fn fetch_lastfm_infos(
     username: String,
     artist_p: String,
     title_p: String,
+    locale: &Locale,
+    lang: &str,
 ) -> Result<String, Box<dyn Error + Send + Sync>> {
     let artist_req = task::spawn(api_requester::fetch_lastfm_artist(
         username.clone().into(),
@@ -1737,11 +4397,15 @@ async fn fetch_lastfm_infos(
         .await?
         .map(|e| {
             format!(
-                "🎙️ {}:\n{} plays\n{} 🌎 listeners\n{} 🌎 scrobbles",
+                "🎙️ {}:\n{}",
                 e.name,
-                e.user_playcount.to_formatted_string(&Locale::en),
-                e.listeners.to_formatted_string(&Locale::en),
-                e.playcount.to_formatted_string(&Locale::en)
+                tr!(
+                    lang,
+                    "info-lastfm",
+                    plays = e.user_playcount.to_formatted_string(locale),
+                    listeners = e.listeners.to_formatted_string(locale),
+                    scrobbles = e.playcount.to_formatted_string(locale)
+                )
             )
         })
         .unwrap_or_default();
@@ -1749,19 +4413,66 @@ async fn fetch_lastfm_infos(
         .await?
         .map(|e| {
             format!(
-                "🎵 {} ({}):\n{} plays\n{} 🌎 listeners\n{} 🌎 scrobbles",
+                "🎵 {} ({}):\n{}",
                 e.name,
                 if e.duration > 0 {
                     utils::human_readable_duration(e.duration)
                 } else {
                     "??:??".to_string()
                 },
-                e.user_playcount.to_formatted_string(&Locale::en),
-                e.listeners.to_formatted_string(&Locale::en),
-                e.playcount.to_formatted_string(&Locale::en)
+                tr!(
+                    lang,
+                    "info-lastfm",
+                    plays = e.user_playcount.to_formatted_string(locale),
+                    listeners = e.listeners.to_formatted_string(locale),
+                    scrobbles = e.playcount.to_formatted_string(locale)
+                )
+            )
+        })
+        .unwrap_or_else(|_| tr!(lang, "info-track-failed"));
+
+    let text = format!("{track}\n\n{artist}");
+
+    Ok(text)
+}
+
+async fn fetch_listenbrainz_infos(
+    artist_p: String,
+    title_p: String,
+    locale: &Locale,
+    lang: &str,
+) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let (track, artist) = api_requester::fetch_listenbrainz_track(&artist_p, &title_p).await?;
+
+    let track = format!(
+        "🎵 {} ({}):\n{}",
+        track.name,
+        if track.duration > 0 {
+            utils::human_readable_duration(track.duration)
+        } else {
+            "??:??".to_string()
+        },
+        tr!(
+            lang,
+            "info-listenbrainz",
+            listeners = track.listeners.to_formatted_string(locale),
+            listens = track.playcount.to_formatted_string(locale)
+        )
+    );
+    let artist = artist
+        .map(|e| {
+            format!(
+                "🎙️ {}:\n{}",
+                e.name,
+                tr!(
+                    lang,
+                    "info-listenbrainz",
+                    listeners = e.listeners.to_formatted_string(locale),
+                    listens = e.playcount.to_formatted_string(locale)
+                )
             )
         })
-        .unwrap_or_else(|_| "Failed to fetch track info".to_string());
+        .unwrap_or_default();
 
     let text = format!("{track}\n\n{artist}");
 
@@ -1769,14 +4480,27 @@ async fn fetch_lastfm_infos(
 }
 
 async fn callback_handler(bot: Bot, q: CallbackQuery) -> Result<(), Box<dyn Error + Send + Sync>> {
-    let callback_data = q.data.as_ref().unwrap();
-    let splits: Vec<&str> = callback_data.splitn(3, ' ').collect();
-    let allowed_user_id: u64 = splits[0].parse()?;
-    let data = splits[1];
-    let arg = if splits.len() == 3 {
-        splits[2].to_lowercase()
-    } else {
-        "".to_owned()
+    let callback_data = q.data.as_deref().unwrap_or_default();
+    let user = DB.lock().unwrap().fetch_user(q.from.id.0);
+    let lang = i18n::lang(
+        &q.from,
+        user.as_ref(),
+        q.regular_message().map(|x| x.chat.id),
+    );
+    let decoded = Callback::decode(callback_data, &DB.lock().unwrap());
+    let Callback {
+        allowed_user_id,
+        payload,
+    } = match decoded {
+        Ok(callback) => callback,
+        Err(e) => {
+            log::warn!("undecodable callback data {callback_data:?}: {e}");
+            bot.answer_callback_query(q.id)
+                .text(tr!(lang, "button-outdated"))
+                .show_alert(true)
+                .await?;
+            return Ok(());
+        }
     };
     let from = &q.from;
     let regular_message = &q.regular_message();
@@ -1786,27 +4510,59 @@ async fn callback_handler(bot: Bot, q: CallbackQuery) -> Result<(), Box<dyn Erro
     // message content and message date will not be available if the message is too old.
     if is_old {
         bot.answer_callback_query(q.id)
-            .text(consts::MESSAGE_TOO_OLD)
+            .text(tr!(lang, "message-too-old"))
             .await?;
         return Ok(());
     }
 
     // 0 means everyone is allowed to click
     if allowed_user_id != 0 && allowed_user_id != from.id.0 {
-        bot.answer_callback_query(q.id).text(consts::NO).await?;
+        bot.answer_callback_query(q.id)
+            .text(tr!(lang, "no"))
+            .await?;
         return Ok(());
     };
 
-    if data == "set" && regular_message.is_some() {
-        set_command(&bot, regular_message.unwrap(), Some(from), &arg, true).await?;
+    if let (Payload::Set { username, api_type }, Some(regular_message)) =
+        (&payload, regular_message)
+    {
+        set_command(&bot, regular_message, Some(from), username, *api_type, true).await?;
         return Ok(());
     }
 
-    let user = DB.lock().unwrap().fetch_user(from.id.0);
+    if let (Payload::ChatSettings(action), Some(regular_message)) = (&payload, regular_message) {
+        let member = bot
+            .get_chat_member(regular_message.chat.id, from.id)
+            .await?;
+        if !member.is_privileged() {
+            bot.answer_callback_query(q.id)
+                .text(tr!(lang, "admins-only"))
+                .show_alert(true)
+                .await?;
+            return Ok(());
+        }
+        chatsettings_command(&bot, regular_message, from, Some(action.clone()), true).await?;
+        track("callback_chatsettings", from.into()).await;
+        return Ok(());
+    }
+
+    // old buttons of a command that got disabled since
+    if let Some(regular_message) = regular_message {
+        let settings = DB
+            .lock()
+            .unwrap()
+            .fetch_chat_settings(regular_message.chat.id.0);
+        if settings.is_disabled(payload.name()) {
+            bot.answer_callback_query(q.id)
+                .text(tr!(lang, "no"))
+                .await?;
+            return Ok(());
+        }
+    }
 
     if user.is_none() {
         bot.answer_callback_query(q.id)
-            .text(consts::NOT_REGISTERED)
+            .text(tr!(lang, "not-registered"))
             .show_alert(true)
             .await?;
         return Ok(());
@@ -1814,224 +4570,219 @@ async fn callback_handler(bot: Bot, q: CallbackQuery) -> Result<(), Box<dyn Erro
 
     let user = user.unwrap();
 
-    match data {
-        "status" => {
+    match &payload {
+        Payload::Status(status_type) => {
             status_command(
                 &bot,
                 *regular_message,
                 inline_message_id,
                 Some(from),
                 true,
-                arg.parse().unwrap_or(StatusType::Compact),
+                *status_type,
                 true,
                 user,
             )
             .await?;
         }
-        "status_refresh" => {
+        Payload::StatusRefresh(status_type) => {
             let res = status_command(
                 &bot,
                 *regular_message,
                 inline_message_id,
                 Some(from),
                 true,
-                arg.parse().unwrap_or(StatusType::Compact),
+                *status_type,
                 false,
                 user,
             )
             .await;
             if res.is_err() {
                 bot.answer_callback_query(q.id)
-                    .text(consts::MESSAGE_UNMODIFIED)
+                    .text(tr!(lang, "message-unmodified"))
                     .await?;
             }
         }
-        "info" => {
-            // regular_message must not be None here
+        Payload::Info { artist, title } => {
+            let (artist, title) = (artist.clone(), title.clone());
 
-            match regular_message {
-                None => {
+            let locale = user.locale();
+            let infos = match user.api_type() {
+                ApiType::Lastfm => {
+                    fetch_lastfm_infos(user.account_username, artist, title, &locale, lang).await
+                }
+                ApiType::Listenbrainz => {
+                    fetch_listenbrainz_infos(artist, title, &locale, lang).await
+                }
+                ApiType::Librefm => {
                     bot.answer_callback_query(q.id)
-                        .text(consts::NO)
-                        .show_alert(true)
+                        .text(tr!(lang, "no"))
                         .await?;
                     return Ok(());
                 }
+            }
+            .unwrap_or_else(|_| tr!(lang, "not-found"));
 
-                Some(regular_message) => {
-                    if user.api_type() == ApiType::Lastfm {
-                        let msg_text = regular_message.text().unwrap_or_default().to_string();
-                        let itatic_entity =
-                            utils::find_first_entity(regular_message, MessageEntityKind::Italic);
-                        let bold_entity =
-                            utils::find_first_entity(regular_message, MessageEntityKind::Bold);
-
-                        if itatic_entity.is_none() || bold_entity.is_none() {
-                            bot.answer_callback_query(q.id)
-                                .text(consts::NOT_FOUND)
-                                .await?;
-                            return Ok(());
-                        }
-
-                        let ita = itatic_entity.unwrap();
-                        let bol = bold_entity.unwrap();
-
-                        let artist = utils::slice_tg_string(
-                            msg_text.clone(),
-                            ita.offset,
-                            ita.length + ita.offset,
-                        );
-                        let title =
-                            utils::slice_tg_string(msg_text, bol.offset, bol.length + bol.offset);
-
-                        if artist.is_none() || title.is_none() {
-                            bot.answer_callback_query(q.id)
-                                .text(consts::NOT_FOUND)
-                                .await?;
-                            return Ok(());
-                        }
+            bot.answer_callback_query(q.id)
+                .text(infos)
+                .show_alert(true)
+                .await?;
+        }
 
-                        let lastfm_username = user.account_username;
+        Payload::Collage { period: None, .. } => {
+            period_chooser(
+                &bot,
+                *regular_message,
+                inline_message_id,
+                from.into(),
+                true,
+                payload.clone(),
+            )
+            .await?;
+        }
 
-                        let infos =
-                            fetch_lastfm_infos(lastfm_username, artist.unwrap(), title.unwrap())
-                                .await
-                                .unwrap_or(consts::NOT_FOUND.to_owned());
-                        bot.answer_callback_query(q.id)
-                            .text(infos)
-                            .show_alert(true)
-                            .await?;
-                    } else {
-                        bot.answer_callback_query(q.id).text(consts::NO).await?;
-                    }
-                }
-            }
+        Payload::Collage { size: None, .. } => {
+            size_chooser(
+                &bot,
+                *regular_message,
+                inline_message_id,
+                from.into(),
+                true,
+                payload.clone(),
+            )
+            .await?;
         }
 
-        "collage" => {
-            let arg_splits: Vec<&str> = arg.split(' ').collect();
-            if arg_splits.is_empty() {
-                period_chooser(
-                    &bot,
-                    *regular_message,
-                    inline_message_id,
-                    from.into(),
-                    true,
-                    data,
-                )
-                .await?;
-            } else if arg_splits.len() == 1 {
-                size_chooser(
-                    &bot,
-                    *regular_message,
-                    inline_message_id,
-                    from.into(),
-                    true,
-                    &format!("{} {}", data, arg_splits[0]),
-                )
-                .await?;
+        Payload::Collage {
+            period: Some(period),
+            size: Some(size),
+            style,
+        } => {
+            // the file is sent separately, so keep the collage's keyboard as it is
+            if style.as_file {
+                bot.answer_callback_query(q.id.clone())
+                    .text(tr!(lang, "loading"))
+                    .await?;
             } else {
-                let keyboard =
-                    InlineKeyboardMarkup::new(vec![vec![InlineKeyboardButton::callback(
-                        "⌛",
-                        "0 loading",
-                    )]]);
+                let keyboard = InlineKeyboardMarkup::new(vec![vec![callback_button(
+                    "⌛",
+                    0,
+                    Payload::Loading,
+                )]]);
 
                 utils::edit_markup(&bot, *regular_message, inline_message_id.as_ref(), keyboard)
                     .await?;
-
-                collage_command(
-                    &bot,
-                    *regular_message,
-                    inline_message_id,
-                    from.into(),
-                    true,
-                    &arg,
-                    user,
-                )
-                .await?;
             }
+
+            collage_command(
+                &bot,
+                *regular_message,
+                inline_message_id,
+                from.into(),
+                true,
+                *period,
+                *size,
+                *style,
+                user,
+            )
+            .await?;
         }
 
-        "random" => {
-            let arg_splits: Vec<&str> = arg.split(' ').collect();
+        Payload::Random {
+            entry_type: None, ..
+        }
+        | Payload::Topkek {
+            entry_type: None, ..
+        } => {
+            type_chooser(
+                &bot,
+                *regular_message,
+                inline_message_id,
+                from.into(),
+                true,
+                payload.clone(),
+            )
+            .await?;
+        }
 
-            if arg_splits.len() != 2 {
-                period_chooser(
-                    &bot,
-                    *regular_message,
-                    inline_message_id,
-                    from.into(),
-                    true,
-                    &format!("{} {}", data, arg_splits[0]),
-                )
-                .await?;
-            } else {
-                random_command(
-                    &bot,
-                    *regular_message,
-                    inline_message_id,
-                    from.into(),
-                    true,
-                    &arg,
-                    user,
-                )
-                .await?;
-            }
+        Payload::Random { period: None, .. } | Payload::Topkek { period: None, .. } => {
+            period_chooser(
+                &bot,
+                *regular_message,
+                inline_message_id,
+                from.into(),
+                true,
+                payload.clone(),
+            )
+            .await?;
         }
 
-        "topkek" => {
-            let arg_splits: Vec<&str> = arg.split(' ').collect();
-            if arg_splits.len() != 2 {
-                period_chooser(
-                    &bot,
-                    *regular_message,
-                    inline_message_id,
-                    from.into(),
-                    true,
-                    &format!("{} {}", data, arg_splits[0]),
-                )
-                .await?;
-            } else {
-                topkek_command(
-                    &bot,
-                    *regular_message,
-                    inline_message_id,
-                    from.into(),
-                    true,
-                    &arg,
-                    user,
-                )
-                .await?;
-            }
+        Payload::Random {
+            entry_type: Some(entry_type),
+            period: Some(period),
+        } => {
+            random_command(
+                &bot,
+                *regular_message,
+                inline_message_id,
+                from.into(),
+                true,
+                *entry_type,
+                *period,
+                user,
+            )
+            .await?;
+        }
+
+        Payload::Topkek {
+            entry_type: Some(entry_type),
+            period: Some(period),
+            as_chart,
+            count,
+            page,
+        } => {
+            topkek_command(
+                &bot,
+                *regular_message,
+                inline_message_id,
+                from.into(),
+                true,
+                *entry_type,
+                *period,
+                *as_chart,
+                *count,
+                *page,
+                user,
+            )
+            .await?;
         }
 
-        "preferences" => {
+        Payload::Preferences(action) => {
             preferences_command(
                 &bot,
                 *regular_message,
                 inline_message_id,
                 from.into(),
                 true,
-                &arg,
+                Some(*action),
                 user,
             )
             .await?;
         }
 
-        "loading" => {
+        Payload::Loading => {
             bot.answer_callback_query(q.id)
-                .text(consts::LOADING)
+                .text(tr!(lang, "loading"))
                 .await?;
         }
 
-        _ => {
-            bot.answer_callback_query(q.id).text(consts::NO).await?;
-            log::error!("{data} unhandled");
+        Payload::Set { .. } | Payload::ChatSettings(_) => {
+            bot.answer_callback_query(q.id)
+                .text(tr!(lang, "no"))
+                .await?;
         }
     }
 
-    track(&format!("callback_{data}"), from.into()).await;
+    track(&format!("callback_{}", payload.name()), from.into()).await;
 
     Ok(())
 }
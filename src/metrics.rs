@@ -0,0 +1,212 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::Write,
+    sync::{LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{config, health};
+
+const PREFIX: &str = "lastfmrobot";
+// upper bounds in seconds, +Inf is added when rendering
+const BUCKETS: [f64; 10] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 25.0, 60.0];
+// nothing sent to this server needs more than the request line
+const MAX_REQUEST_LEN: usize = 4096;
+
+#[derive(Default)]
+struct Histogram {
+    buckets: [u64; BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+// keyed by metric name, then by the rendered label set
+#[derive(Default)]
+struct Registry {
+    counters: BTreeMap<&'static str, BTreeMap<String, u64>>,
+    histograms: BTreeMap<&'static str, BTreeMap<String, Histogram>>,
+}
+
+static REGISTRY: LazyLock<Mutex<Registry>> = LazyLock::new(Default::default);
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn render_labels(labels: &[(&str, &str)]) -> String {
+    labels
+        .iter()
+        .map(|(k, v)| format!("{k}=\"{}\"", escape(v)))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+pub fn inc(name: &'static str, labels: &[(&str, &str)]) {
+    *REGISTRY
+        .lock()
+        .unwrap()
+        .counters
+        .entry(name)
+        .or_default()
+        .entry(render_labels(labels))
+        .or_default() += 1;
+}
+
+pub fn observe(name: &'static str, labels: &[(&str, &str)], elapsed: Duration) {
+    let seconds = elapsed.as_secs_f64();
+    let mut registry = REGISTRY.lock().unwrap();
+    let histogram = registry
+        .histograms
+        .entry(name)
+        .or_default()
+        .entry(render_labels(labels))
+        .or_default();
+    for (bucket, bound) in histogram.buckets.iter_mut().zip(BUCKETS) {
+        if seconds <= bound {
+            *bucket += 1;
+        }
+    }
+    histogram.sum += seconds;
+    histogram.count += 1;
+}
+
+// the prometheus text format
+fn render() -> String {
+    let registry = REGISTRY.lock().unwrap();
+    let mut out = String::new();
+    let with = |labels: &str, extra: &str| match (labels.is_empty(), extra.is_empty()) {
+        (true, true) => String::new(),
+        (false, true) => format!("{{{labels}}}"),
+        (true, false) => format!("{{{extra}}}"),
+        (false, false) => format!("{{{labels},{extra}}}"),
+    };
+
+    for (name, series) in &registry.counters {
+        let _ = writeln!(out, "# TYPE {PREFIX}_{name} counter");
+        for (labels, value) in series {
+            let _ = writeln!(out, "{PREFIX}_{name}{} {value}", with(labels, ""));
+        }
+    }
+
+    for (name, series) in &registry.histograms {
+        let _ = writeln!(out, "# TYPE {PREFIX}_{name} histogram");
+        for (labels, histogram) in series {
+            for (bucket, bound) in histogram.buckets.iter().zip(BUCKETS) {
+                let le = format!("le=\"{bound}\"");
+                let _ = writeln!(out, "{PREFIX}_{name}_bucket{} {bucket}", with(labels, &le));
+            }
+            let le = "le=\"+Inf\"";
+            let _ = writeln!(
+                out,
+                "{PREFIX}_{name}_bucket{} {}",
+                with(labels, le),
+                histogram.count
+            );
+            let _ = writeln!(
+                out,
+                "{PREFIX}_{name}_sum{} {}",
+                with(labels, ""),
+                histogram.sum
+            );
+            let _ = writeln!(
+                out,
+                "{PREFIX}_{name}_count{} {}",
+                with(labels, ""),
+                histogram.count
+            );
+        }
+    }
+
+    out
+}
+
+// handler latency, and which telegram api errors the handlers ran into
+pub async fn timed(
+    handler: &'static str,
+    future: impl Future<Output = Result<(), Box<dyn Error + Send + Sync>>>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let started = Instant::now();
+    let result = future.await;
+    observe(
+        "handler_seconds",
+        &[("handler", handler)],
+        started.elapsed(),
+    );
+
+    if let Err(e) = &result {
+        let kind = match e.downcast_ref::<teloxide::RequestError>() {
+            Some(teloxide::RequestError::Api(_)) => "api",
+            Some(teloxide::RequestError::RetryAfter(_)) => "retry_after",
+            Some(teloxide::RequestError::Network(_)) => "network",
+            Some(_) => "other",
+            None => "not_telegram",
+        };
+        inc(
+            "handler_errors_total",
+            &[("handler", handler), ("kind", kind)],
+        );
+    }
+    result
+}
+
+async fn respond(mut stream: TcpStream) -> std::io::Result<()> {
+    let mut buffer = vec![0; MAX_REQUEST_LEN];
+    let len = stream.read(&mut buffer).await?;
+    let request = String::from_utf8_lossy(&buffer[..len]);
+    let path = request.split_whitespace().nth(1).unwrap_or_default();
+
+    let (status, content_type, body) = match path {
+        "/metrics" => ("200 OK", "text/plain; version=0.0.4", render()),
+        "/health" => match health::report() {
+            (true, body) => ("200 OK", "application/json", body),
+            (false, body) => ("503 Service Unavailable", "application/json", body),
+        },
+        _ => ("404 Not Found", "text/plain", "not found\n".to_string()),
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+// /metrics and /health, does nothing unless METRICS_ADDRESS is set
+pub async fn serve() {
+    if config::METRICS_ADDRESS.is_empty() {
+        return;
+    }
+
+    let listener = match TcpListener::bind(config::METRICS_ADDRESS).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            log::error!(
+                "metrics server can't listen on {} {e}",
+                config::METRICS_ADDRESS
+            );
+            return;
+        }
+    };
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(async move {
+                    if let Err(e) = respond(stream).await {
+                        log::warn!("metrics request failed {e}");
+                    }
+                });
+            }
+            Err(e) => log::warn!("metrics accept failed {e}"),
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use std::{
+    collections::HashSet,
+    error::Error,
+    sync::LazyLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+use num_format::{Locale, ToFormattedString};
+use teloxide::{
+    prelude::*,
+    types::{LinkPreviewOptions, ParseMode},
+};
+
+use crate::{
+    Bot, DB,
+    api_requester::{self, ApiType},
+    db, i18n, tr, utils,
+};
+
+pub const SCROBBLE_MILESTONES: &[u64] = &[
+    1_000, 5_000, 10_000, 25_000, 50_000, 100_000, 250_000, 500_000, 1_000_000,
+];
+pub const ARTIST_MILESTONES: &[u64] = &[100, 250, 500, 1_000, 2_500, 5_000, 10_000];
+
+const DAY_SECS: u64 = 60 * 60 * 24;
+const STREAK_WINDOW_SECS: u64 = DAY_SECS * 7;
+const STREAK_PAGES_PER_WINDOW: usize = 3;
+// anything longer is shown as "365+"
+pub const MAX_STREAK_DAYS: usize = 365;
+
+const NOTIFY_INTERVAL: Duration = Duration::from_secs(60 * 60);
+// spacing between users so a big run doesn't hammer the scrobble apis
+const NOTIFY_USER_DELAY: Duration = Duration::from_secs(2);
+
+// flexing twice in a row shouldn't page through weeks of scrobbles again
+static STREAKS: LazyLock<moka::future::Cache<String, usize>> = LazyLock::new(|| {
+    moka::future::Cache::builder()
+        .max_capacity(10_000)
+        .time_to_live(Duration::from_secs(60 * 30))
+        .build()
+});
+
+// highest milestone that value has passed
+pub fn reached(value: u64, milestones: &[u64]) -> Option<u64> {
+    milestones.iter().rev().find(|&&x| value >= x).copied()
+}
+
+pub fn next(value: u64, milestones: &[u64]) -> Option<u64> {
+    milestones.iter().find(|&&x| value < x).copied()
+}
+
+fn scrobble_day(date: u64, timezone: &Tz) -> Option<NaiveDate> {
+    DateTime::<Utc>::from_timestamp(date as i64, 0).map(|x| x.with_timezone(timezone).date_naive())
+}
+
+// consecutive days with a scrobble, ending today or yesterday so an evening listener
+// doesn't lose their streak every morning
+async fn current_streak_uncached(
+    username: &str,
+    api_type: &ApiType,
+    timezone: &Tz,
+) -> Result<usize, Box<dyn Error + Send + Sync>> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let today = Utc::now().with_timezone(timezone).date_naive();
+    let mut days = HashSet::new();
+    let mut cursor = now;
+
+    loop {
+        let from = cursor.saturating_sub(STREAK_WINDOW_SECS);
+        let (tracks, complete) = api_requester::fetch_scrobbles_between(
+            username,
+            api_type,
+            from,
+            cursor,
+            STREAK_PAGES_PER_WINDOW,
+        )
+        .await?;
+        let dates = tracks.iter().filter_map(|x| x.date).collect::<Vec<_>>();
+        days.extend(dates.iter().filter_map(|&x| scrobble_day(x, timezone)));
+
+        // everything newer than this has been seen
+        let scanned_until = if complete {
+            from
+        } else {
+            dates.iter().min().copied().unwrap_or(from)
+        };
+
+        let mut day = if days.contains(&today) {
+            today
+        } else {
+            today.pred_opt().unwrap()
+        };
+        let mut streak = 0;
+        while days.contains(&day) {
+            streak += 1;
+            day = day.pred_opt().unwrap();
+        }
+
+        // the first missing day has been fully scanned, so it's a real gap
+        let gap_confirmed = scrobble_day(scanned_until, timezone).is_some_and(|x| x < day);
+        if gap_confirmed
+            || streak >= MAX_STREAK_DAYS
+            || scanned_until == 0
+            || scanned_until >= cursor
+        {
+            return Ok(streak.min(MAX_STREAK_DAYS));
+        }
+        cursor = scanned_until;
+    }
+}
+
+pub async fn current_streak(
+    username: &str,
+    api_type: &ApiType,
+    timezone: &Tz,
+) -> Result<usize, Box<dyn Error + Send + Sync>> {
+    let key = format!("{api_type} {timezone} {}", username.to_lowercase());
+    if let Some(streak) = STREAKS.get(&key).await {
+        return Ok(streak);
+    }
+
+    let streak = current_streak_uncached(username, api_type, timezone).await?;
+    STREAKS.insert(key, streak).await;
+    Ok(streak)
+}
+
+pub fn format_streak(streak: usize) -> String {
+    if streak >= MAX_STREAK_DAYS {
+        format!("{MAX_STREAK_DAYS}+")
+    } else {
+        streak.to_string()
+    }
+}
+
+// subject is None when telling the user themselves
+fn announcement(
+    subject: Option<&str>,
+    scrobbles: Option<u64>,
+    artists: Option<u64>,
+    locale: &Locale,
+    lang: &str,
+) -> String {
+    let reached = [
+        scrobbles.map(|x| tr!(lang, "count-plays", count = x.to_formatted_string(locale))),
+        artists.map(|x| tr!(lang, "count-artists", count = x.to_formatted_string(locale))),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(&tr!(lang, "and"));
+
+    match subject {
+        Some(subject) => tr!(
+            lang,
+            "milestone-announcement",
+            name = subject,
+            reached = reached
+        ),
+        None => tr!(lang, "milestone-notification", reached = reached),
+    }
+}
+
+async fn check_user(bot: &Bot, user: &db::User) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let info = api_requester::fetch_user_info(&user.account_username, &user.api_type()).await?;
+    let scrobbles = reached(info.playcount, SCROBBLE_MILESTONES).unwrap_or_default();
+    let artists = reached(info.artist_count, ARTIST_MILESTONES).unwrap_or_default();
+
+    let previous = DB.lock().unwrap().fetch_milestones(user.tg_user_id);
+    DB.lock().unwrap().upsert_milestones(
+        user.tg_user_id,
+        &user.account_username,
+        scrobbles,
+        artists,
+    )?;
+
+    // first check after opting in or relinking, only remember where they are
+    let (old_scrobbles, old_artists) = match previous {
+        Some((username, s, a)) if username == user.account_username => (s, a),
+        _ => return Ok(()),
+    };
+
+    let new_scrobbles = Some(scrobbles).filter(|&x| x > old_scrobbles);
+    let new_artists = Some(artists).filter(|&x| x > old_artists);
+    if new_scrobbles.is_none() && new_artists.is_none() {
+        return Ok(());
+    }
+
+    // no telegram user at hand here, only the stored language applies
+    let language = user
+        .language()
+        .and_then(i18n::supported)
+        .unwrap_or(i18n::DEFAULT_LANG);
+    let lang = i18n::voice(language, i18n::personality(Some(user), None));
+
+    // they may never have started the bot in private, groups still get told
+    if let Err(e) = bot
+        .send_message(
+            UserId(user.tg_user_id),
+            announcement(None, new_scrobbles, new_artists, &user.locale(), lang),
+        )
+        .await
+    {
+        log::warn!("milestone notification to {} failed {e}", user.tg_user_id);
+    }
+
+    let chats = DB.lock().unwrap().fetch_milestone_chats(user.tg_user_id);
+    for chat_id in chats {
+        // their telegram name like elsewhere in groups, the account name may be hidden
+        let name = match bot
+            .get_chat_member(ChatId(chat_id), UserId(user.tg_user_id))
+            .await
+        {
+            Ok(member) if member.is_present() => utils::name_with_link(&member.user, user),
+            Ok(_) => continue,
+            Err(e) => {
+                log::warn!("milestone announcement to {chat_id} skipped {e}");
+                continue;
+            }
+        };
+        let lang = i18n::voice(
+            language,
+            i18n::personality(Some(user), Some(ChatId(chat_id))),
+        );
+        if let Err(e) = bot
+            .send_message(
+                ChatId(chat_id),
+                announcement(
+                    Some(&name),
+                    new_scrobbles,
+                    new_artists,
+                    &user.locale(),
+                    lang,
+                ),
+            )
+            .parse_mode(ParseMode::Html)
+            .link_preview_options(LinkPreviewOptions {
+                is_disabled: true,
+                url: None,
+                prefer_small_media: false,
+                prefer_large_media: false,
+                show_above_text: false,
+            })
+            .await
+        {
+            log::warn!("milestone announcement to {chat_id} failed {e}");
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn run_notifier(bot: Bot) {
+    let mut interval = tokio::time::interval(NOTIFY_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let users = DB.lock().unwrap().fetch_milestone_users();
+        for user in users {
+            if let Err(e) = check_user(&bot, &user).await {
+                log::warn!("milestone check for {} failed {e}", user.tg_user_id);
+            }
+            tokio::time::sleep(NOTIFY_USER_DELAY).await;
+        }
+    }
+}
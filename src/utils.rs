@@ -5,22 +5,26 @@ use std::{
 };
 
 use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use num_format::Locale;
 use teloxide::{
     adaptors::Throttle,
     payloads::{
         EditMessageMediaInlineSetters, EditMessageMediaSetters,
         EditMessageReplyMarkupInlineSetters, EditMessageReplyMarkupSetters,
-        EditMessageTextInlineSetters, EditMessageTextSetters, SendMessageSetters, SendPhotoSetters,
+        EditMessageTextInlineSetters, EditMessageTextSetters, SendDocumentSetters,
+        SendMessageSetters, SendPhotoSetters,
     },
     requests::Requester,
     types::{
-        InlineKeyboardMarkup, InputFile, InputMedia, InputMediaPhoto, LinkPreviewOptions, Message,
-        MessageEntity, MessageEntityKind, ParseMode, ReplyParameters,
+        InlineKeyboardMarkup, InputFile, InputMedia, InputMediaDocument, InputMediaPhoto,
+        LinkPreviewOptions, Message, ParseMode, ReplyParameters,
     },
 };
 
 use crate::{
     api_requester::{ApiType, EntryType, TimePeriod},
+    collage::CollageStyle,
     config, db,
 };
 
@@ -30,16 +34,6 @@ pub fn replace_html_symbols(text: &str) -> String {
         .replace('>', "&gt;")
 }
 
-pub fn find_first_entity(msg: &Message, entity_kind: MessageEntityKind) -> Option<MessageEntity> {
-    let entity = msg
-        .entities()
-        .unwrap_or_default()
-        .iter()
-        .find(|&e| e.kind == entity_kind);
-
-    entity.cloned()
-}
-
 pub fn choose_the_from(
     msg: Option<&Message>,
     inline_from: Option<&teloxide::types::User>,
@@ -57,48 +51,57 @@ pub fn human_readable_duration(ms: u64) -> String {
 }
 
 pub fn name_with_link(tg_user: &teloxide::types::User, db_user: &db::User) -> String {
-    let name = replace_html_symbols(&tg_user.first_name);
+    account_name_with_link(&tg_user.first_name, db_user)
+}
+
+pub fn account_name_with_link(name: &str, db_user: &db::User) -> String {
+    let name = replace_html_symbols(name);
     if db_user.profile_shown {
-        match db_user.api_type() {
-            ApiType::Lastfm => format!(
-                "<a href=\"https://www.last.fm/user/{}\">{}</a>",
-                db_user.account_username, name
-            ),
-            ApiType::Librefm => format!(
-                "<a href=\"https://libre.fm/user/{}\">{}</a>",
-                db_user.account_username, name
-            ),
-            ApiType::Listenbrainz => format!(
-                "<a href=\"https://listenbrainz.org/user/{}\">{}</a>",
-                db_user.account_username, name
-            ),
-        }
+        let base_url = match db_user.api_type() {
+            ApiType::Lastfm => "https://www.last.fm/user",
+            ApiType::Librefm => "https://libre.fm/user",
+            ApiType::Listenbrainz => "https://listenbrainz.org/user",
+        };
+        // usernames typed as service:username never went through the service
+        format!(
+            "<a href=\"{}/{}\">{}</a>",
+            base_url,
+            url_escape::encode_component(&db_user.account_username),
+            name
+        )
     } else {
         name
     }
 }
 
-pub fn slice_tg_string(s: String, start: usize, end: usize) -> Option<String> {
-    let mut utf16_len = 0;
-    let mut start_byte = None;
-    let mut end_byte = None;
-
-    for (i, ch) in s.char_indices() {
-        if utf16_len == start {
-            start_byte = Some(i);
-        }
-        if utf16_len == end {
-            end_byte = Some(i);
-            break;
-        }
-        utf16_len += ch.len_utf16();
-    }
+pub fn hashtags(tags: impl Iterator<Item = String>) -> String {
+    tags.map(|t| {
+        t.replace(
+            &['(', ')', ',', '\"', '.', ';', ':', '\'', '-', ' ', '/'][..],
+            "_",
+        )
+    })
+    .filter(|x| !x.is_empty())
+    .map(|x| format!("#{x}"))
+    .collect::<Vec<_>>()
+    .join(" ")
+}
 
-    if start_byte.is_none() || end_byte.is_none() {
-        return None;
-    }
+// "artist - title", also with an em dash
+pub fn split_artist_title(arg: &str) -> (String, Option<String>) {
+    let splits = if arg.contains(" — ") {
+        arg.splitn(2, " — ").collect::<Vec<_>>()
+    } else {
+        arg.splitn(2, " - ").collect::<Vec<_>>()
+    };
 
-    Some(s[start_byte.unwrap()..end_byte.unwrap()].to_string())
+    (
+        splits[0].trim().to_string(),
+        splits
+            .get(1)
+            .map(|x| x.trim().to_string())
+            .filter(|x| !x.is_empty()),
+    )
 }
 
 pub async fn send_or_edit_message(
@@ -253,6 +256,50 @@ pub async fn send_or_edit_photo(
     Ok(())
 }
 
+// documents are always sent as a new message, except for inline messages which get replaced
+pub async fn send_or_edit_document(
+    bot: &Throttle<teloxide::Bot>,
+    media: InputMediaDocument,
+    msg: Option<&Message>,
+    inline_message_id: Option<&String>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if let Some(msg) = msg {
+        let x = bot
+            .send_document(msg.chat.id, media.media)
+            .reply_parameters(ReplyParameters::new(msg.id).allow_sending_without_reply())
+            .parse_mode(ParseMode::Html)
+            .caption(media.caption.unwrap_or_default());
+        match x.await {
+            Ok(_) => {}
+            Err(e) => {
+                if e.to_string()
+                    .contains("Bad Request: not enough rights to send documents to the chat")
+                {
+                    bot.leave_chat(msg.chat.id).await?;
+                }
+                return Err(Box::new(e));
+            }
+        }
+    } else if let Some(inline_message_id) = inline_message_id {
+        // send the document to the dump chat to get a file id.
+        let dump_msg = bot
+            .send_document(config::INLINE_IMAGES_DUMP_CHAT_ID.to_string(), media.media)
+            .await?;
+
+        let document = dump_msg
+            .document()
+            .ok_or("The dump chat message has no document")?;
+        let new_media = InputMediaDocument::new(InputFile::file_id(document.file.id.clone()))
+            .caption(media.caption.unwrap_or_default())
+            .parse_mode(ParseMode::Html);
+
+        bot.edit_message_media_inline(inline_message_id, InputMedia::Document(new_media))
+            .await?;
+    }
+
+    Ok(())
+}
+
 pub async fn edit_markup(
     bot: &Throttle<teloxide::Bot>,
     msg: Option<&Message>,
@@ -291,9 +338,9 @@ pub fn convert_to_timeago(seconds: u64) -> String {
     FORMATTER.convert(duration)
 }
 
-pub fn format_epoch_secs(seconds: u64, with_time: bool) -> String {
+pub fn format_epoch_secs(seconds: u64, with_time: bool, timezone: &Tz) -> String {
     let d = UNIX_EPOCH + Duration::from_secs(seconds);
-    let datetime = DateTime::<Utc>::from(d);
+    let datetime = DateTime::<Utc>::from(d).with_timezone(timezone);
     let fmt_str = if with_time {
         "%Y-%m-%d %H:%M:%S"
     } else {
@@ -302,24 +349,44 @@ pub fn format_epoch_secs(seconds: u64, with_time: bool) -> String {
     datetime.format(fmt_str).to_string()
 }
 
+// IANA names, case doesn't matter
+pub fn parse_timezone(name: &str) -> Option<Tz> {
+    name.parse().ok().or_else(|| {
+        chrono_tz::TZ_VARIANTS
+            .iter()
+            .find(|x| x.name().eq_ignore_ascii_case(name))
+            .copied()
+    })
+}
+
+// num_format names like en, de or en-IN
+pub fn parse_locale(name: &str) -> Option<Locale> {
+    let name = name.replace('_', "-");
+    Locale::available_names()
+        .iter()
+        .find(|x| x.eq_ignore_ascii_case(&name))
+        .and_then(|x| Locale::from_name(*x).ok())
+}
+
 // collage 3 1month
-pub fn parse_collage_arg(arg: &str) -> (u32, TimePeriod, EntryType, bool) {
-    let splits = arg.splitn(4, ' ').collect::<Vec<&str>>();
+pub fn parse_collage_arg(arg: &str) -> (u32, TimePeriod, EntryType, CollageStyle) {
+    let splits = arg.split_whitespace().collect::<Vec<&str>>();
 
     let mut size = 3;
     let mut period = TimePeriod::AllTime;
-    let mut no_text = false;
+    let mut style = CollageStyle::default();
     let mut entry_type = EntryType::Album;
 
     let mut size_found = false;
     let mut period_found = false;
     let mut entry_type_found = false;
 
-    if splits.contains(&"notext") || splits.contains(&"nonames") || splits.contains(&"clean") {
-        no_text = true;
-    }
-
     for split in splits {
+        // style words can contain period letters, so they go first
+        if style.parse_word(split) {
+            continue;
+        }
+
         if !entry_type_found {
             entry_type_found = true;
             if split.starts_with("artist") {
@@ -401,5 +468,5 @@ pub fn parse_collage_arg(arg: &str) -> (u32, TimePeriod, EntryType, bool) {
         }
     }
 
-    (size, period, entry_type, no_text)
+    (size, period, entry_type, style)
 }
@@ -0,0 +1,332 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use chrono_tz::Tz;
+use image::imageops::FilterType;
+use image::{Rgba, RgbaImage};
+use imageproc::drawing::{draw_filled_rect_mut, draw_text_mut};
+use imageproc::rect::Rect;
+use num_format::{Locale, ToFormattedString};
+
+use crate::api_requester::Track;
+use crate::collage::{self, FONT, OutputFormat};
+use crate::{i18n, tr};
+
+const TOP_COUNT: usize = 5;
+
+const IMAGE_WIDTH: u32 = 1000;
+const IMAGE_HEIGHT: u32 = 1000;
+const MARGIN_PX: u32 = 40;
+const COVER_PX: u32 = 260;
+const COLUMN_WIDTH: u32 = (IMAGE_WIDTH - MARGIN_PX * 3) / 2;
+
+const BACKGROUND_COLOR: Rgba<u8> = Rgba([20, 20, 24, 255]);
+const PRIMARY_COLOR: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const SECONDARY_COLOR: Rgba<u8> = Rgba([170, 170, 170, 255]);
+const ACCENT_COLOR: Rgba<u8> = Rgba([213, 16, 7, 255]);
+
+pub struct Wrapped {
+    pub year: i32,
+    pub total: usize,
+    // (name, plays), most played first
+    pub top_artists: Vec<(String, usize)>,
+    pub top_albums: Vec<(String, usize)>,
+    pub top_tracks: Vec<(String, usize)>,
+    pub top_album_art_url: Option<String>,
+    // None when the service can't tell what was listened to before the year
+    pub new_artists: Option<Vec<(String, usize)>>,
+    pub new_artists_count: usize,
+    pub busiest_month: Option<(u32, usize)>,
+    pub busiest_day: Option<(NaiveDate, usize)>,
+    // (first day, last day, length in days)
+    pub longest_streak: Option<(NaiveDate, NaiveDate, usize)>,
+}
+
+fn top_counts(keys: impl Iterator<Item = String>) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for key in keys {
+        *counts.entry(key).or_default() += 1;
+    }
+    let mut counts = counts.into_iter().collect::<Vec<_>>();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+fn scrobble_date(track: &Track, timezone: &Tz) -> Option<NaiveDate> {
+    DateTime::<Utc>::from_timestamp(track.date? as i64, 0)
+        .map(|x| x.with_timezone(timezone).date_naive())
+}
+
+pub fn build(
+    year: i32,
+    scrobbles: &[Track],
+    artists_before: Option<HashSet<String>>,
+    timezone: &Tz,
+) -> Wrapped {
+    let artists = top_counts(scrobbles.iter().map(|x| x.artist.clone()));
+    let albums = top_counts(
+        scrobbles
+            .iter()
+            .filter_map(|x| Some(format!("{} — {}", x.album.as_ref()?, x.artist))),
+    );
+    let tracks = top_counts(
+        scrobbles
+            .iter()
+            .map(|x| format!("{} — {}", x.name, x.artist)),
+    );
+
+    let top_album_art_url = albums.first().and_then(|(top_album, _)| {
+        scrobbles
+            .iter()
+            .filter(|x| {
+                x.album
+                    .as_ref()
+                    .is_some_and(|album| format!("{} — {}", album, x.artist) == *top_album)
+            })
+            .find_map(|x| x.album_art_url.clone())
+    });
+
+    let new_artists = artists_before.map(|before| {
+        artists
+            .iter()
+            .filter(|(name, _)| !before.contains(&name.to_lowercase()))
+            .cloned()
+            .collect::<Vec<_>>()
+    });
+    let new_artists_count = new_artists.as_ref().map(|x| x.len()).unwrap_or_default();
+
+    let dates = scrobbles
+        .iter()
+        .filter_map(|x| scrobble_date(x, timezone))
+        .collect::<Vec<_>>();
+
+    let mut months: HashMap<u32, usize> = HashMap::new();
+    let mut days: HashMap<NaiveDate, usize> = HashMap::new();
+    for date in &dates {
+        *months.entry(date.month()).or_default() += 1;
+        *days.entry(*date).or_default() += 1;
+    }
+    let busiest_month = months
+        .into_iter()
+        .max_by_key(|x| (x.1, std::cmp::Reverse(x.0)));
+    let busiest_day = days
+        .iter()
+        .map(|(date, count)| (*date, *count))
+        .max_by_key(|x| (x.1, std::cmp::Reverse(x.0)));
+
+    let mut listening_days = days.into_keys().collect::<Vec<_>>();
+    listening_days.sort();
+    let mut longest_streak: Option<(NaiveDate, NaiveDate, usize)> = None;
+    let mut current: Option<(NaiveDate, NaiveDate, usize)> = None;
+    for day in listening_days {
+        current = match current {
+            Some((start, end, len)) if end.succ_opt() == Some(day) => Some((start, day, len + 1)),
+            _ => Some((day, day, 1)),
+        };
+        if current.map(|x| x.2) > longest_streak.map(|x| x.2) {
+            longest_streak = current;
+        }
+    }
+
+    Wrapped {
+        year,
+        total: scrobbles.len(),
+        top_artists: artists.into_iter().take(TOP_COUNT).collect(),
+        top_albums: albums.into_iter().take(TOP_COUNT).collect(),
+        top_tracks: tracks.into_iter().take(TOP_COUNT).collect(),
+        top_album_art_url,
+        new_artists: new_artists.map(|x| x.into_iter().take(TOP_COUNT).collect()),
+        new_artists_count,
+        busiest_month,
+        busiest_day,
+        longest_streak,
+    }
+}
+
+pub async fn create_summary_image(
+    wrapped: &Wrapped,
+    name: &str,
+    locale: &Locale,
+    lang: &str,
+) -> Result<Vec<u8>, anyhow::Error> {
+    let mut summary = RgbaImage::from_pixel(IMAGE_WIDTH, IMAGE_HEIGHT, BACKGROUND_COLOR);
+
+    let mut draw = |x: u32, y: u32, size: f32, color: Rgba<u8>, max_width: u32, text: &str| {
+        draw_text_mut(
+            &mut summary,
+            color,
+            x as i32,
+            y as i32,
+            size,
+            &*FONT,
+            &collage::fit_text(text, size, max_width),
+        );
+    };
+
+    let full_width = IMAGE_WIDTH - MARGIN_PX * 2;
+    draw(
+        MARGIN_PX,
+        MARGIN_PX,
+        40.0,
+        PRIMARY_COLOR,
+        full_width,
+        &tr!(lang, "wrapped-heading", name = name, year = wrapped.year),
+    );
+    draw(
+        MARGIN_PX,
+        MARGIN_PX + 60,
+        72.0,
+        ACCENT_COLOR,
+        full_width,
+        &wrapped.total.to_formatted_string(locale),
+    );
+    draw(
+        MARGIN_PX,
+        MARGIN_PX + 140,
+        28.0,
+        SECONDARY_COLOR,
+        full_width,
+        &tr!(lang, "wrapped-scrobbles"),
+    );
+
+    let mut draw_list = |x: u32, y: u32, title: &str, entries: &[(String, usize)]| {
+        draw(x, y, 30.0, PRIMARY_COLOR, COLUMN_WIDTH, title);
+        for (i, (entry, count)) in entries.iter().enumerate() {
+            draw(
+                x,
+                y + 46 + 36 * i as u32,
+                24.0,
+                SECONDARY_COLOR,
+                COLUMN_WIDTH,
+                &format!(
+                    "{}. {} ({})",
+                    i + 1,
+                    entry,
+                    count.to_formatted_string(locale)
+                ),
+            );
+        }
+    };
+
+    let lists_y = MARGIN_PX + 210;
+    let right_x = MARGIN_PX * 2 + COLUMN_WIDTH;
+    draw_list(
+        MARGIN_PX,
+        lists_y,
+        &tr!(lang, "wrapped-top-artists"),
+        &wrapped.top_artists,
+    );
+    draw_list(
+        right_x,
+        lists_y,
+        &tr!(lang, "wrapped-top-tracks"),
+        &wrapped.top_tracks,
+    );
+
+    let albums_y = lists_y + 46 + 36 * TOP_COUNT as u32 + 30;
+    let cover = match &wrapped.top_album_art_url {
+        Some(url) => collage::fetch_images(&[url.as_str()])
+            .await
+            .into_iter()
+            .next()
+            .and_then(|x| x.ok())
+            .and_then(|bytes| image::load_from_memory(&bytes).ok()),
+        None => None,
+    };
+    let albums_x = match cover {
+        Some(cover) => {
+            let mut cover = cover
+                .resize_to_fill(COVER_PX, COVER_PX, FilterType::Lanczos3)
+                .to_rgba8();
+            collage::round_corners(&mut cover, 12);
+            image::imageops::overlay(&mut summary, &cover, MARGIN_PX.into(), albums_y.into());
+            MARGIN_PX * 2 + COVER_PX
+        }
+        None => MARGIN_PX,
+    };
+    let albums_width = IMAGE_WIDTH - albums_x - MARGIN_PX;
+    draw_text_mut(
+        &mut summary,
+        PRIMARY_COLOR,
+        albums_x as i32,
+        albums_y as i32,
+        30.0,
+        &*FONT,
+        &tr!(lang, "wrapped-top-albums"),
+    );
+    for (i, (album, count)) in wrapped.top_albums.iter().enumerate() {
+        draw_text_mut(
+            &mut summary,
+            SECONDARY_COLOR,
+            albums_x as i32,
+            (albums_y + 46 + 36 * i as u32) as i32,
+            24.0,
+            &*FONT,
+            &collage::fit_text(
+                &format!(
+                    "{}. {} ({})",
+                    i + 1,
+                    album,
+                    count.to_formatted_string(locale)
+                ),
+                24.0,
+                albums_width,
+            ),
+        );
+    }
+
+    let facts_y = albums_y + COVER_PX + 40;
+    draw_filled_rect_mut(
+        &mut summary,
+        Rect::at(MARGIN_PX as i32, facts_y as i32 - 20).of_size(full_width, 2),
+        SECONDARY_COLOR,
+    );
+
+    let mut facts = vec![];
+    if let Some((month, count)) = wrapped.busiest_month {
+        facts.push(tr!(
+            lang,
+            "wrapped-busiest-month",
+            month = i18n::month(lang, month),
+            scrobbles = count.to_formatted_string(locale)
+        ));
+    }
+    if let Some((day, count)) = wrapped.busiest_day {
+        facts.push(tr!(
+            lang,
+            "wrapped-busiest-day",
+            day = tr!(
+                lang,
+                "date-short",
+                day = day.day(),
+                month = i18n::month_short(lang, day.month())
+            ),
+            scrobbles = count.to_formatted_string(locale)
+        ));
+    }
+    if let Some((_, _, len)) = wrapped.longest_streak {
+        facts.push(tr!(lang, "wrapped-longest-streak", days = len));
+    }
+    if wrapped.new_artists.is_some() {
+        facts.push(tr!(
+            lang,
+            "wrapped-new-artists",
+            count = wrapped.new_artists_count.to_formatted_string(locale)
+        ));
+    }
+    for (i, fact) in facts.iter().enumerate() {
+        let x = if i % 2 == 0 { MARGIN_PX } else { right_x };
+        let y = facts_y + 40 * (i as u32 / 2);
+        draw_text_mut(
+            &mut summary,
+            PRIMARY_COLOR,
+            x as i32,
+            y as i32,
+            26.0,
+            &*FONT,
+            &collage::fit_text(fact, 26.0, COLUMN_WIDTH),
+        );
+    }
+
+    collage::encode_image(summary, OutputFormat::Jpeg, None)
+}